@@ -0,0 +1,194 @@
+use crate::network::BtcNetwork;
+use crate::test_suite::TestError;
+use bip39::{Language, Mnemonic};
+use bitcoin::bip32::{DerivationPath, ExtendedPrivKey};
+use bitcoin::key::PrivateKey;
+use bitcoin::secp256k1::Secp256k1;
+use bitcoin::{Address, PublicKey};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// How the run's BTC address is derived from the mnemonic and encoded.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum AddressScheme {
+    /// Native segwit P2WPKH (`bc1q…`)
+    #[default]
+    Bip84,
+    /// Taproot P2TR key-path spend (`bc1p…`)
+    Bip86,
+    /// Nested segwit P2SH-P2WPKH (`3…`)
+    Bip49,
+}
+
+impl AddressScheme {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AddressScheme::Bip84 => "bip84",
+            AddressScheme::Bip86 => "bip86",
+            AddressScheme::Bip49 => "bip49",
+        }
+    }
+
+    fn purpose(self) -> u32 {
+        match self {
+            AddressScheme::Bip84 => 84,
+            AddressScheme::Bip86 => 86,
+            AddressScheme::Bip49 => 49,
+        }
+    }
+
+    /// The scheme's standard path for the first receive address of
+    /// `account` on `network`.
+    pub fn default_path(self, network: BtcNetwork, account: u32) -> String {
+        format!("m/{}'/{}'/{}'/0/0", self.purpose(), network.coin_type(), account)
+    }
+}
+
+impl fmt::Display for AddressScheme {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AddressScheme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bip84" => Ok(AddressScheme::Bip84),
+            "bip86" => Ok(AddressScheme::Bip86),
+            "bip49" => Ok(AddressScheme::Bip49),
+            _ => Err(format!("Unknown address scheme '{}'", s)),
+        }
+    }
+}
+
+/// Derives the address at `path` for `mnemonic` and `passphrase`, encoded for
+/// `scheme` on `network`.
+pub fn derive_address(
+    mnemonic: &str,
+    passphrase: &str,
+    network: BtcNetwork,
+    scheme: AddressScheme,
+    path: &str,
+) -> Result<String, TestError> {
+    // Parse the mnemonic
+    let mnemonic = Mnemonic::parse_in_normalized(Language::English, mnemonic)
+        .map_err(|e| TestError::crypto(format!("Invalid mnemonic: {}", e)))?;
+
+    // Generate seed from mnemonic
+    let seed = mnemonic.to_seed(passphrase);
+
+    let secp = Secp256k1::new();
+    let network = network.bitcoin();
+    let master = ExtendedPrivKey::new_master(network, &seed)
+        .map_err(|e| TestError::crypto(format!("Failed to create master key: {}", e)))?;
+
+    let path = DerivationPath::from_str(path)
+        .map_err(|e| TestError::crypto(format!("Invalid derivation path '{}': {}", path, e)))?;
+
+    let child = master
+        .derive_priv(&secp, &path)
+        .map_err(|e| TestError::crypto(format!("Failed to derive child key: {}", e)))?;
+
+    let private_key = PrivateKey::new(child.private_key, network);
+    let public_key = PublicKey::from_private_key(&secp, &private_key);
+
+    let address = match scheme {
+        AddressScheme::Bip84 => Address::p2wpkh(&public_key, network),
+        AddressScheme::Bip49 => Address::p2shwpkh(&public_key, network),
+        AddressScheme::Bip86 => {
+            let (internal_key, _) = public_key.inner.x_only_public_key();
+            Ok(Address::p2tr(&secp, internal_key, None, network))
+        }
+    }
+    .map_err(|e| TestError::crypto(format!("Failed to create address: {}", e)))?;
+
+    Ok(address.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The mnemonic the BIP84, BIP49 and BIP86 test vectors use.
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn derive(network: BtcNetwork, scheme: AddressScheme, account: u32, passphrase: &str) -> String {
+        let path = scheme.default_path(network, account);
+        derive_address(MNEMONIC, passphrase, network, scheme, &path).unwrap()
+    }
+
+    #[test]
+    fn paths_use_the_network_coin_type() {
+        assert_eq!(AddressScheme::Bip84.default_path(BtcNetwork::Mainnet, 0), "m/84'/0'/0'/0/0");
+        assert_eq!(AddressScheme::Bip86.default_path(BtcNetwork::Mainnet, 2), "m/86'/0'/2'/0/0");
+        for network in [BtcNetwork::Testnet, BtcNetwork::Signet, BtcNetwork::Regtest] {
+            assert_eq!(AddressScheme::Bip49.default_path(network, 0), "m/49'/1'/0'/0/0");
+        }
+    }
+
+    #[test]
+    fn mainnet_matches_the_published_vectors() {
+        let mainnet = BtcNetwork::Mainnet;
+        assert_eq!(
+            derive(mainnet, AddressScheme::Bip84, 0, ""),
+            "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu"
+        );
+        assert_eq!(derive(mainnet, AddressScheme::Bip49, 0, ""), "37VucYSaXLCAsxYyAPfbSi9eh4iEcbShgf");
+        assert_eq!(
+            derive(mainnet, AddressScheme::Bip86, 0, ""),
+            "bc1p5cyxnuxmeuwuvkwfem96lqzszd02n6xdcjrs20cac6yqjjwudpxqkedrcr"
+        );
+        // The second receive address from the BIP84 vectors
+        let second = derive_address(MNEMONIC, "", mainnet, AddressScheme::Bip84, "m/84'/0'/0'/0/1").unwrap();
+        assert_eq!(second, "bc1qnjg0jd8228aq7egyzacy8cys3knf9xvrerkf9g");
+    }
+
+    #[test]
+    fn test_networks_match_the_published_vectors() {
+        assert_eq!(
+            derive(BtcNetwork::Testnet, AddressScheme::Bip84, 0, ""),
+            "tb1q6rz28mcfaxtmd6v789l9rrlrusdprr9pqcpvkl"
+        );
+        assert_eq!(
+            derive(BtcNetwork::Testnet, AddressScheme::Bip49, 0, ""),
+            "2Mww8dCYPUpKHofjgcXcBCEGmniw9CoaiD2"
+        );
+        // Signet shares testnet's encoding; regtest only changes the HRP
+        assert_eq!(
+            derive(BtcNetwork::Signet, AddressScheme::Bip84, 0, ""),
+            "tb1q6rz28mcfaxtmd6v789l9rrlrusdprr9pqcpvkl"
+        );
+        let regtest = derive(BtcNetwork::Regtest, AddressScheme::Bip84, 0, "");
+        assert!(regtest.starts_with("bcrt1q6rz28mcfaxtmd6v789l9rrlrusdprr9p"), "{}", regtest);
+        assert!(derive(BtcNetwork::Testnet, AddressScheme::Bip86, 0, "").starts_with("tb1p"));
+        assert!(derive(BtcNetwork::Regtest, AddressScheme::Bip86, 0, "").starts_with("bcrt1p"));
+    }
+
+    #[test]
+    fn passphrase_and_account_change_the_address() {
+        let published = "bc1qcr8te4kr609gcawutmrza0j4xv80jy8z306fyu";
+        let with_passphrase = derive(BtcNetwork::Mainnet, AddressScheme::Bip84, 0, "TREZOR");
+        let next_account = derive(BtcNetwork::Mainnet, AddressScheme::Bip84, 1, "");
+        assert_ne!(with_passphrase, published);
+        assert_ne!(next_account, published);
+        assert_ne!(with_passphrase, next_account);
+        assert!(with_passphrase.starts_with("bc1q") && next_account.starts_with("bc1q"));
+    }
+
+    #[test]
+    fn invalid_input_is_a_crypto_error() {
+        let bad_mnemonic = derive_address("abandon about", "", BtcNetwork::Testnet, AddressScheme::Bip84, "m/0");
+        assert!(bad_mnemonic.unwrap_err().message.contains("Invalid mnemonic"));
+        let bad_path = derive_address(MNEMONIC, "", BtcNetwork::Testnet, AddressScheme::Bip84, "m/x");
+        assert!(bad_path.unwrap_err().message.contains("Invalid derivation path"));
+    }
+}
@@ -0,0 +1,204 @@
+use crate::events::{self, LogEvent, LogLevel};
+use chrono::Utc;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use serde_json::{json, Map};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+const LOG_FILE_NAME: &str = "events.jsonl.gz";
+
+/// Stores per-run logs as gzip-compressed JSON lines under
+/// `<dir>/<run id>/`, keeping them out of the results table.
+pub struct ArtifactStore {
+    dir: PathBuf,
+    max_log_bytes: usize,
+}
+
+/// Query parameters for reading a slice of a run's log. `tail` returns the
+/// last N lines; otherwise `offset` and `length` select a byte range.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LogRange {
+    pub tail: Option<usize>,
+    pub offset: Option<usize>,
+    pub length: Option<usize>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LogChunk {
+    pub total_bytes: usize,
+    pub offset: usize,
+    pub length: usize,
+    pub content: String,
+}
+
+impl ArtifactStore {
+    pub fn new(dir: &str, max_log_bytes: usize) -> Self {
+        ArtifactStore {
+            dir: PathBuf::from(dir),
+            max_log_bytes,
+        }
+    }
+
+    fn log_path(&self, run_id: &str) -> PathBuf {
+        self.dir.join(run_id).join(LOG_FILE_NAME)
+    }
+
+    pub fn write_logs(&self, run_id: &str, events: &[LogEvent]) -> io::Result<()> {
+        let content = truncate_lines(&events::to_json_lines(events), self.max_log_bytes);
+
+        let path = self.log_path(run_id);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut encoder = GzEncoder::new(fs::File::create(&path)?, Compression::default());
+        encoder.write_all(content.as_bytes())?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /// Returns the run's log as JSON lines, or `None` if no log file exists.
+    pub fn read_logs(&self, run_id: &str) -> io::Result<Option<String>> {
+        // Run ids come from request paths; never let them escape the store
+        if !run_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Ok(None);
+        }
+        let path = self.log_path(run_id);
+        if !Path::new(&path).exists() {
+            return Ok(None);
+        }
+        let mut content = String::new();
+        GzDecoder::new(fs::File::open(&path)?).read_to_string(&mut content)?;
+        Ok(Some(content))
+    }
+}
+
+/// Caps `content` at roughly `max_bytes` on line boundaries, keeping the
+/// head and tail of the log and replacing the middle with a marker event.
+fn truncate_lines(content: &str, max_bytes: usize) -> String {
+    if content.len() <= max_bytes {
+        return content.to_string();
+    }
+
+    let budget = max_bytes / 2;
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+
+    let mut head_len = 0;
+    let mut head = 0;
+    while head < lines.len() && head_len + lines[head].len() <= budget {
+        head_len += lines[head].len();
+        head += 1;
+    }
+
+    let mut tail_len = 0;
+    let mut tail = lines.len();
+    while tail > head && tail_len + lines[tail - 1].len() <= budget {
+        tail_len += lines[tail - 1].len();
+        tail -= 1;
+    }
+
+    let omitted_bytes = content.len() - head_len - tail_len;
+    let mut marker = LogEvent {
+        timestamp: Utc::now(),
+        level: LogLevel::Warn,
+        step: None,
+        message: format!(
+            "[log truncated: {} lines, {} bytes omitted]",
+            tail - head,
+            omitted_bytes
+        ),
+        fields: Map::new(),
+    };
+    marker.fields.insert("truncated_lines".to_string(), json!(tail - head));
+    marker.fields.insert("truncated_bytes".to_string(), json!(omitted_bytes));
+
+    let mut truncated = lines[..head].concat();
+    truncated.push_str(&events::to_json_lines(&[marker]));
+    truncated.push_str(&lines[tail..].concat());
+    truncated
+}
+
+/// Applies a `LogRange` to the full log content. Byte ranges are widened to
+/// the nearest character boundaries.
+pub fn slice_logs(content: &str, range: &LogRange) -> LogChunk {
+    let total_bytes = content.len();
+
+    let (mut start, mut end) = if let Some(tail) = range.tail {
+        let start = content
+            .trim_end_matches('\n')
+            .rmatch_indices('\n')
+            .nth(tail.saturating_sub(1))
+            .map(|(index, _)| index + 1)
+            .unwrap_or(0);
+        (if tail == 0 { total_bytes } else { start }, total_bytes)
+    } else {
+        let start = range.offset.unwrap_or(0).min(total_bytes);
+        let end = range
+            .length
+            .map(|length| start.saturating_add(length).min(total_bytes))
+            .unwrap_or(total_bytes);
+        (start, end)
+    };
+
+    while !content.is_char_boundary(start) {
+        start -= 1;
+    }
+    while !content.is_char_boundary(end) {
+        end += 1;
+    }
+
+    LogChunk {
+        total_bytes,
+        offset: start,
+        length: end - start,
+        content: content[start..end].to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(tail: Option<usize>, offset: Option<usize>, length: Option<usize>) -> LogRange {
+        LogRange { tail, offset, length }
+    }
+
+    #[test]
+    fn tail_returns_the_last_lines() {
+        let chunk = slice_logs("a\nb\nc\n", &range(Some(2), None, None));
+        assert_eq!(chunk.content, "b\nc\n");
+        assert_eq!((chunk.offset, chunk.total_bytes), (2, 6));
+        assert_eq!(slice_logs("a\nb\n", &range(Some(5), None, None)).content, "a\nb\n");
+        assert_eq!(slice_logs("a\nb\n", &range(Some(0), None, None)).content, "");
+    }
+
+    #[test]
+    fn byte_ranges_widen_to_character_boundaries() {
+        // "é" takes bytes 1 and 2
+        let chunk = slice_logs("aéb", &range(None, Some(2), Some(2)));
+        assert_eq!(chunk.content, "éb");
+        assert_eq!((chunk.offset, chunk.length), (1, 3));
+        let chunk = slice_logs("aéb", &range(None, Some(0), Some(2)));
+        assert_eq!(chunk.content, "aé");
+        assert_eq!(slice_logs("abc", &range(None, Some(10), None)).content, "");
+    }
+
+    #[test]
+    fn truncation_keeps_head_and_tail_lines() {
+        let content: String = (0..10).map(|line| format!("line {}\n", line)).collect();
+        assert_eq!(truncate_lines(&content, content.len()), content);
+
+        let truncated = truncate_lines(&content, 28);
+        let lines: Vec<&str> = truncated.lines().collect();
+        assert_eq!(lines[..2], ["line 0", "line 1"]);
+        assert_eq!(lines[3..], ["line 8", "line 9"]);
+        let marker: LogEvent = serde_json::from_str(lines[2]).unwrap();
+        assert_eq!(marker.fields["truncated_lines"], 6);
+        assert_eq!(marker.fields["truncated_bytes"], 42);
+    }
+}
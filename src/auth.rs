@@ -0,0 +1,159 @@
+use crate::cli_binary::sha256_hex;
+use crate::config::AuthConfig;
+use crate::{ApiResponse, AppState};
+use actix_web::body::MessageBody;
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::{header, Method};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, FromRequest, HttpMessage, HttpRequest, HttpResponse};
+use chrono::Utc;
+use futures::future::{ready, Ready};
+use serde::{Deserialize, Serialize};
+
+/// Access levels for API tokens. `Admin` can do everything `ReadOnly` can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    ReadOnly,
+    Admin,
+}
+
+/// The authenticated identity behind a request, available to handlers as an
+/// extractor once the `authenticate` middleware has run.
+#[derive(Debug, Clone, Serialize)]
+pub struct Caller {
+    pub name: String,
+    pub role: Role,
+}
+
+impl Caller {
+    pub fn is_admin(&self) -> bool {
+        self.role == Role::Admin
+    }
+}
+
+impl FromRequest for Caller {
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(
+            req.extensions()
+                .get::<Caller>()
+                .cloned()
+                .ok_or_else(|| actix_web::error::ErrorUnauthorized("Unauthenticated request")),
+        )
+    }
+}
+
+/// Paths that stay reachable without a token.
+fn is_public(path: &str) -> bool {
+    matches!(path, "/" | "/health" | "/openapi.json" | "/docs") || path.starts_with("/docs/")
+}
+
+/// The minimum role for a request: reads need a read-only token, anything
+/// that spends funds, reveals mnemonics or changes state needs an admin one.
+fn required_role(method: &Method, path: &str) -> Role {
+    if path == "/last-successful-mnemonic" || path.starts_with("/audit") {
+        Role::Admin
+    } else if method == Method::GET || method == Method::HEAD {
+        Role::ReadOnly
+    } else {
+        Role::Admin
+    }
+}
+
+impl AuthConfig {
+    /// Looks up a presented bearer token by its SHA-256.
+    pub fn find_caller(&self, token: &str) -> Option<Caller> {
+        let hash = sha256_hex(token.as_bytes());
+        self.tokens
+            .iter()
+            .find(|t| t.sha256.eq_ignore_ascii_case(&hash))
+            .map(|t| Caller {
+                name: t.name.clone(),
+                role: t.role,
+            })
+    }
+}
+
+fn deny(req: ServiceRequest, status: actix_web::http::StatusCode, message: String) -> ServiceResponse {
+    let response = ApiResponse {
+        success: false,
+        message,
+        data: None::<()>,
+        timestamp: Utc::now().to_rfc3339(),
+    };
+    req.into_response(HttpResponse::build(status).json(response))
+}
+
+/// Authenticates bearer tokens, enforces the route's required role and
+/// records every request in the audit log. With no tokens configured every
+/// caller is treated as an anonymous admin.
+pub async fn authenticate(
+    req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<impl MessageBody>, Error> {
+    let data = req
+        .app_data::<web::Data<AppState>>()
+        .cloned()
+        .expect("AppState is registered");
+    let method = req.method().clone();
+    let path = req.path().to_string();
+
+    let caller = if data.config.auth.tokens.is_empty() {
+        Some(Caller {
+            name: "anonymous".to_string(),
+            role: Role::Admin,
+        })
+    } else {
+        req.headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "))
+            .and_then(|token| data.config.auth.find_caller(token.trim()))
+    };
+
+    let caller_name = caller
+        .as_ref()
+        .map(|c| c.name.clone())
+        .unwrap_or_else(|| "unauthenticated".to_string());
+
+    let response = if is_public(&path) {
+        next.call(req).await?.map_into_left_body()
+    } else {
+        match caller {
+            None => deny(
+                req,
+                actix_web::http::StatusCode::UNAUTHORIZED,
+                "Missing or invalid bearer token".to_string(),
+            )
+            .map_into_right_body(),
+            Some(caller) if caller.role < required_role(&method, &path) => deny(
+                req,
+                actix_web::http::StatusCode::FORBIDDEN,
+                format!("Token '{}' is not allowed to {} {}", caller.name, method, path),
+            )
+            .map_into_right_body(),
+            Some(caller) => {
+                req.extensions_mut().insert(caller);
+                next.call(req).await?.map_into_left_body()
+            }
+        }
+    };
+
+    let status = response.status().as_u16();
+    log::info!("{} {} by {} -> {}", method, path, caller_name, status);
+    if !is_public(&path) {
+        if let Err(e) = data
+            .db
+            .lock()
+            .unwrap()
+            .record_request(&caller_name, method.as_str(), &path, status)
+        {
+            log::error!("Failed to write audit log: {}", e);
+        }
+    }
+
+    Ok(response)
+}
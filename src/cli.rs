@@ -0,0 +1,212 @@
+use crate::address::AddressScheme;
+use crate::cli_binary::sha256_hex;
+use crate::config::Config;
+use crate::scenario::{self, NamedScenario};
+use crate::network::BtcNetwork;
+use crate::test_suite::TestSuite;
+use clap::{Args, Parser, Subcommand};
+use rand::{rngs::OsRng, RngCore};
+use std::path::Path;
+
+#[derive(Debug, Parser)]
+#[command(name = "borrower-cli-tester", about = "Borrower CLI test server and runner")]
+pub struct Cli {
+    /// Path to the TOML config file (overrides CONFIG_PATH)
+    #[arg(long, global = true)]
+    pub config: Option<String>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Start the HTTP server (default when no subcommand is given)
+    Serve,
+    /// Run the test suite once without starting the HTTP server
+    Run(RunArgs),
+    /// Print the SHA-256 to put in `auth.tokens` for an API token
+    HashToken {
+        /// Token to hash; a random one is generated if omitted
+        token: Option<String>,
+    },
+    /// Serve a stand-in Esplora API for testing the balance check and the
+    /// confirmation wait offline
+    MockEsplora {
+        #[arg(long, default_value_t = 3002)]
+        port: u16,
+        /// Balance every queried address reports
+        #[arg(long, default_value_t = 100000)]
+        balance_sats: u64,
+        /// Seconds after an address is first queried before its balance
+        /// moves from the mempool to the chain
+        #[arg(long, default_value_t = 0)]
+        confirm_after_secs: u64,
+        /// Seconds between blocks on the mock chain
+        #[arg(long, default_value_t = 10)]
+        block_secs: u64,
+    },
+}
+
+#[derive(Debug, Args)]
+pub struct RunArgs {
+    /// Use this mnemonic instead of generating a new one
+    #[arg(long)]
+    pub mnemonic: Option<String>,
+
+    /// Length of a generated mnemonic: 12, 18 or 24 words
+    #[arg(long)]
+    pub words: Option<usize>,
+
+    /// BIP39 passphrase for the wallet; `--save` requires `server.secret_key`
+    #[arg(long)]
+    pub passphrase: Option<String>,
+
+    /// BIP44 account index for the derivation path
+    #[arg(long)]
+    pub account_index: Option<u32>,
+
+    /// Network to run on (defaults to `suite.network`)
+    #[arg(long, value_enum)]
+    pub network: Option<BtcNetwork>,
+
+    /// Address derivation scheme (defaults to `suite.address_scheme`)
+    #[arg(long, value_enum)]
+    pub address_scheme: Option<AddressScheme>,
+
+    /// Derivation path overriding the scheme's standard one
+    #[arg(long)]
+    pub derivation_path: Option<String>,
+
+    /// Run this scenario file from `suite.scenarios_dir`, or negative
+    /// scenario, instead of the borrow/repay flow
+    #[arg(long, conflicts_with = "scenario_file")]
+    pub scenario: Option<String>,
+
+    /// Run the scenario file at this path
+    #[arg(long)]
+    pub scenario_file: Option<String>,
+
+    /// Loan amount passed to `borrow init`
+    #[arg(long)]
+    pub loan_amount: Option<u64>,
+
+    /// LTV ratio in basis points passed to `borrow init`
+    #[arg(long)]
+    pub ltv_ratio_bp: Option<u32>,
+
+    /// Loan duration in days passed to `borrow init`
+    #[arg(long)]
+    pub loan_duration_days: Option<u32>,
+
+    /// Save the result to the configured database
+    #[arg(long)]
+    pub save: bool,
+
+    /// Print the full result as JSON when the run finishes
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Executes a single suite run and returns the process exit code.
+pub async fn run(mut config: Config, args: RunArgs) -> i32 {
+    if let Some(amount) = args.loan_amount {
+        config.suite.loan.amount = amount;
+    }
+    if let Some(ltv) = args.ltv_ratio_bp {
+        config.suite.loan.ltv_ratio_bp = ltv;
+    }
+    if let Some(days) = args.loan_duration_days {
+        config.suite.loan.duration_days = days;
+    }
+    if let Some(network) = args.network {
+        config.suite.network = network;
+    }
+    if let Some(scheme) = args.address_scheme {
+        config.suite.address_scheme = scheme;
+    }
+    if let Some(words) = args.words {
+        config.suite.mnemonic_words = words;
+    }
+    if let Some(account) = args.account_index {
+        config.suite.account_index = account;
+    }
+    if let Err(e) = config.validate() {
+        log::error!("{}", e);
+        return 2;
+    }
+    if args.save && args.passphrase.is_some() && config.server.secret_key.is_none() {
+        log::error!("Set server.secret_key to save a run with a BIP39 passphrase");
+        return 2;
+    }
+
+    let mut test_suite = TestSuite::new(config.suite.clone());
+    if let Some(mnemonic) = args.mnemonic {
+        test_suite = test_suite.with_mnemonic(mnemonic);
+    }
+    if let Some(path) = args.derivation_path {
+        test_suite = test_suite.with_derivation_path(path);
+    }
+    if let Some(passphrase) = args.passphrase {
+        test_suite = test_suite.with_passphrase(passphrase);
+    }
+    if let Some(path) = args.scenario_file {
+        match scenario::load(Path::new(&path)) {
+            Ok(scenario) => test_suite = test_suite.with_scenario_file(scenario),
+            Err(e) => {
+                log::error!("{}", e);
+                return 2;
+            }
+        }
+    } else if let Some(name) = args.scenario {
+        match NamedScenario::find(&config.suite, &name) {
+            Ok(scenario) => test_suite = scenario.apply(test_suite),
+            Err(e) => {
+                log::error!("{}", e);
+                return 2;
+            }
+        }
+    }
+
+    let mut result = test_suite.run().await;
+    result.triggered_by = Some("cli".to_string());
+
+    if args.save {
+        match config.open_database() {
+            Ok(db) => match db.save_result(&result) {
+                Ok(_) => log::info!("Saved result {} to {}", result.id, config.server.database_path),
+                Err(e) => log::error!("Failed to save test result to database: {}", e),
+            },
+            Err(e) => log::error!("Failed to initialize database: {}", e),
+        }
+    }
+
+    if args.json {
+        match serde_json::to_string_pretty(&result) {
+            Ok(json) => println!("{}", json),
+            Err(e) => log::error!("Failed to serialize result: {}", e),
+        }
+    }
+
+    if result.success {
+        println!("PASSED: {}", result.details);
+        0
+    } else if result.inconclusive {
+        eprintln!("INCONCLUSIVE: {}", result.details);
+        3
+    } else {
+        eprintln!("FAILED: {}", result.details);
+        1
+    }
+}
+
+/// Prints a token and its hash for use in the `auth.tokens` config section.
+pub fn hash_token(token: Option<String>) {
+    let token = token.unwrap_or_else(|| {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    });
+    println!("token:  {}", token);
+    println!("sha256: {}", sha256_hex(token.as_bytes()));
+}
@@ -0,0 +1,104 @@
+use crate::config::CliBinaryConfig;
+use crate::test_suite::TestError;
+use reqwest::Client;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+pub const CLI_FILE_NAME: &str = "loans-borrower-cli";
+
+pub struct InstalledCli {
+    pub path: PathBuf,
+    pub version: String,
+    pub downloaded: bool,
+}
+
+/// Returns the versioned cache path for the pinned CLI, if a version is pinned.
+pub fn managed_path(config: &CliBinaryConfig) -> Option<PathBuf> {
+    config
+        .version
+        .as_ref()
+        .map(|version| Path::new(&config.cache_dir).join(version).join(CLI_FILE_NAME))
+}
+
+pub fn sha256_file(path: &Path) -> io::Result<String> {
+    let bytes = fs::read(path)?;
+    Ok(sha256_hex(&bytes))
+}
+
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Makes sure the pinned CLI version is present in the cache and matches its
+/// pinned checksum, downloading it if needed. Returns `None` when no version
+/// is pinned.
+pub async fn ensure_installed(config: &CliBinaryConfig) -> Result<Option<InstalledCli>, TestError> {
+    let (version, url, expected) = match (&config.version, &config.download_url, &config.sha256) {
+        (Some(version), Some(url), Some(sha256)) => (version, url, sha256.to_lowercase()),
+        (None, _, _) => return Ok(None),
+        _ => {
+            return Err(TestError::process(
+                "suite.cli.download_url and suite.cli.sha256 are required for a pinned CLI",
+            )
+            .retryable(false))
+        }
+    };
+    let path = managed_path(config).expect("version is set");
+
+    // Concurrent runs may find the same stale entry, so one that another run
+    // already removed counts as missing rather than as an error
+    match sha256_file(&path) {
+        Ok(actual) if actual == expected => {
+            return Ok(Some(InstalledCli {
+                path,
+                version: version.clone(),
+                downloaded: false,
+            }));
+        }
+        Ok(_) => {
+            // A corrupt or tampered cache entry is replaced by a fresh download
+            match fs::remove_file(&path) {
+                Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+                _ => {}
+            }
+        }
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e.into()),
+    }
+
+    let url = url.replace("{version}", version);
+    let response = Client::new().get(&url).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(TestError::network(format!("CLI download from {} failed", url))
+            .with_http_status(status.as_u16()));
+    }
+    let bytes = response.bytes().await?;
+
+    let actual = sha256_hex(&bytes);
+    if actual != expected {
+        return Err(TestError::crypto(format!(
+            "CLI {} checksum mismatch: expected {}, got {}",
+            version, expected, actual
+        )));
+    }
+
+    // Write to a temporary file first so concurrent runs never see a partial binary
+    let dir = path.parent().expect("managed path has a parent");
+    fs::create_dir_all(dir)?;
+    let tmp_path = dir.join(format!(".{}.{}", CLI_FILE_NAME, Uuid::new_v4()));
+    fs::write(&tmp_path, &bytes)?;
+    fs::rename(&tmp_path, &path)?;
+
+    Ok(Some(InstalledCli {
+        path,
+        version: version.clone(),
+        downloaded: true,
+    }))
+}
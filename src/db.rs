@@ -1,29 +1,202 @@
+use crate::address::AddressScheme;
+use crate::artifacts::ArtifactStore;
+use crate::events::{self, LogEvent};
+use crate::faucet::FaucetReceipt;
+use crate::load::{LoadReport, LoadRun, LoadSuite};
+use crate::matrix::{CellStatus, MatrixCell, MatrixRun, MatrixSummary};
+use crate::network::BtcNetwork;
+use crate::scenario::ScenarioCheckpoint;
+use crate::secrets::SecretBox;
+use crate::test_suite::{ErrorKind, TestError};
 use chrono::{DateTime, Utc};
-use rusqlite::{params, types::Type, Connection, Result};
+use rusqlite::{params, params_from_iter, types::Type, Connection, Result, Row};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use std::fs;
 use std::path::Path;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct TestResult {
     pub id: String,
     pub success: bool,
     pub details: String,
     pub mnemonic: String,
+    /// BIP39 passphrase, stored encrypted under `server.secret_key`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub passphrase: Option<String>,
+    pub account_index: Option<u32>,
+    /// Pool wallet the run leased, if any
+    pub wallet_id: Option<String>,
+    /// Sats requested from the BTC faucet; unset when the faucet was skipped
+    pub faucet_sats: Option<u64>,
+    /// Wallet balance the Esplora balance check last saw; unset when the
+    /// check is disabled
+    pub btc_balance_sats: Option<u64>,
+    /// Successful faucet requests, with their transaction ids
+    pub faucet_receipts: Vec<FaucetReceipt>,
+    /// The run failed for reasons outside the CLI (a faucet outage, funds
+    /// that never arrived) and says nothing about it
+    pub inconclusive: bool,
+    /// Scenario the run executed; unset for the standard borrow/repay flow
+    pub scenario: Option<String>,
+    /// Text of the scenario file the run executed, as it was when it ran
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scenario_source: Option<String>,
+    /// Unset on results saved before runs could choose a network
+    pub network: Option<BtcNetwork>,
     pub btc_address: String,
+    pub address_scheme: Option<AddressScheme>,
+    pub derivation_path: Option<String>,
+    /// Wallet address reported by the CLI, when its output includes one
+    pub cli_btc_address: Option<String>,
+    /// Whether `cli_btc_address` differs from `btc_address`; unset when the
+    /// CLI reported no address
+    pub address_mismatch: Option<bool>,
     pub lava_pubkey: String,
     pub contract_id: Option<String>,
+    /// Every contract the run created, in order; `contract_id` is the last
+    #[serde(default)]
+    pub contract_ids: Vec<String>,
     pub steps_completed: Vec<String>,
-    pub logs: String,
+    /// Only populated on freshly run results; stored results keep their log
+    /// in the artifact store (see `Database::get_logs`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub logs: Vec<LogEvent>,
     pub timestamp: DateTime<Utc>,
+    pub cli_version: Option<String>,
+    pub cli_sha256: Option<String>,
+    pub error: Option<TestError>,
+    /// Name of the API token (or `cli`) that started the run
+    pub triggered_by: Option<String>,
 }
 
+impl TestResult {
+    /// A passing result with nothing recorded, for tests to fill in.
+    #[cfg(test)]
+    pub fn empty(id: &str) -> Self {
+        TestResult {
+            id: id.to_string(),
+            success: true,
+            details: String::new(),
+            mnemonic: String::new(),
+            passphrase: None,
+            account_index: None,
+            wallet_id: None,
+            faucet_sats: None,
+            btc_balance_sats: None,
+            faucet_receipts: Vec::new(),
+            inconclusive: false,
+            scenario: None,
+            scenario_source: None,
+            network: None,
+            btc_address: String::new(),
+            address_scheme: None,
+            derivation_path: None,
+            cli_btc_address: None,
+            address_mismatch: None,
+            lava_pubkey: String::new(),
+            contract_id: None,
+            contract_ids: Vec::new(),
+            steps_completed: Vec::new(),
+            logs: Vec::new(),
+            timestamp: Utc::now(),
+            cli_version: None,
+            cli_sha256: None,
+            error: None,
+            triggered_by: None,
+        }
+    }
+
+    /// Hides the mnemonic from callers that may not see secrets.
+    pub fn redact_secrets(&mut self) {
+        self.mnemonic = "***".to_string();
+        if self.passphrase.is_some() {
+            self.passphrase = Some("***".to_string());
+        }
+        self.logs.clear();
+    }
+}
+
+/// One authenticated API request.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AuditEntry {
+    pub timestamp: String,
+    pub caller: String,
+    pub method: String,
+    pub path: String,
+    pub status: u16,
+}
+
+/// Optional filters for listing results; unset fields match everything.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ResultFilter {
+    pub success: Option<bool>,
+    pub network: Option<BtcNetwork>,
+    pub address_scheme: Option<AddressScheme>,
+    pub address_mismatch: Option<bool>,
+    /// Failures caused by faucets or funding rather than the CLI
+    pub inconclusive: Option<bool>,
+    pub scenario: Option<String>,
+    /// Runs that created this contract
+    pub contract_id: Option<String>,
+    pub cli_version: Option<String>,
+    pub error_kind: Option<ErrorKind>,
+    pub error_step: Option<u32>,
+    pub retryable: Option<bool>,
+}
+
+/// Pass/fail counts for one CLI version.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CliVersionSummary {
+    pub cli_version: Option<String>,
+    pub cli_sha256: Option<String>,
+    pub total: i64,
+    pub passed: i64,
+    pub failed: i64,
+    pub last_run: String,
+}
+
+/// A funded wallet kept for reuse across runs.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct PooledWallet {
+    pub id: String,
+    pub network: BtcNetwork,
+    pub mnemonic: String,
+    pub btc_address: String,
+    /// Last known balance; unset until the wallet has been funded or checked
+    pub balance_sats: Option<u64>,
+    pub balance_updated_at: Option<String>,
+    /// Set while a run holds the wallet
+    pub leased_at: Option<String>,
+    pub last_used_at: Option<String>,
+    pub runs: u32,
+    pub created_at: String,
+}
+
+impl PooledWallet {
+    pub fn redact_secrets(&mut self) {
+        self.mnemonic = "***".to_string();
+    }
+}
+
+const WALLET_COLUMNS: &str = "id, network, mnemonic, btc_address, balance_sats, balance_updated_at,
+             leased_at, last_used_at, runs, created_at";
+
+const RESULT_COLUMNS: &str = "id, success, details, mnemonic, btc_address, lava_pubkey, 
+             contract_id, steps_completed, timestamp, cli_version, cli_sha256, error, triggered_by,
+             network, address_scheme, derivation_path, cli_btc_address, address_mismatch,
+             passphrase_encrypted, account_index, wallet_id, faucet_sats, btc_balance_sats,
+             faucet_receipts, inconclusive, scenario, scenario_source";
+
 pub struct Database {
     conn: Connection,
+    artifacts: ArtifactStore,
+    secrets: Option<SecretBox>,
 }
 
 impl Database {
-    pub fn new(db_path: &str) -> Result<Self> {
+    pub fn new(db_path: &str, artifacts: ArtifactStore, secrets: Option<SecretBox>) -> Result<Self> {
         // Ensure directory exists
         if let Some(parent) = Path::new(db_path).parent() {
             fs::create_dir_all(parent).map_err(|e| {
@@ -52,15 +225,176 @@ impl Database {
             [],
         )?;
 
-        Ok(Database { conn })
+        // Columns added after the original schema
+        add_column_if_missing(&conn, "test_results", "cli_version", "TEXT")?;
+        add_column_if_missing(&conn, "test_results", "cli_sha256", "TEXT")?;
+        add_column_if_missing(&conn, "test_results", "error", "TEXT")?;
+        add_column_if_missing(&conn, "test_results", "triggered_by", "TEXT")?;
+        add_column_if_missing(&conn, "test_results", "network", "TEXT")?;
+        add_column_if_missing(&conn, "test_results", "address_scheme", "TEXT")?;
+        add_column_if_missing(&conn, "test_results", "derivation_path", "TEXT")?;
+        add_column_if_missing(&conn, "test_results", "cli_btc_address", "TEXT")?;
+        add_column_if_missing(&conn, "test_results", "address_mismatch", "INTEGER")?;
+        add_column_if_missing(&conn, "test_results", "passphrase_encrypted", "TEXT")?;
+        add_column_if_missing(&conn, "test_results", "account_index", "INTEGER")?;
+        add_column_if_missing(&conn, "test_results", "wallet_id", "TEXT")?;
+        add_column_if_missing(&conn, "test_results", "faucet_sats", "INTEGER")?;
+        add_column_if_missing(&conn, "test_results", "btc_balance_sats", "INTEGER")?;
+        add_column_if_missing(&conn, "test_results", "faucet_receipts", "TEXT")?;
+        add_column_if_missing(
+            &conn,
+            "test_results",
+            "inconclusive",
+            "INTEGER NOT NULL DEFAULT 0",
+        )?;
+        add_column_if_missing(&conn, "test_results", "scenario", "TEXT")?;
+        add_column_if_missing(&conn, "test_results", "scenario_source", "TEXT")?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS wallet_pool (
+                id TEXT PRIMARY KEY,
+                network TEXT NOT NULL,
+                mnemonic TEXT NOT NULL,
+                btc_address TEXT NOT NULL,
+                balance_sats INTEGER,
+                balance_updated_at TEXT,
+                leased_at TEXT,
+                last_used_at TEXT,
+                runs INTEGER NOT NULL DEFAULT 0,
+                created_at TEXT NOT NULL,
+                UNIQUE (network, mnemonic)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS matrix_runs (
+                id TEXT PRIMARY KEY,
+                requested_by TEXT NOT NULL,
+                network TEXT NOT NULL,
+                concurrency INTEGER NOT NULL,
+                skip_faucet INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                finished_at TEXT
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS matrix_cells (
+                matrix_id TEXT NOT NULL REFERENCES matrix_runs (id),
+                cell_index INTEGER NOT NULL,
+                ltv_ratio_bp INTEGER NOT NULL,
+                duration_days INTEGER NOT NULL,
+                amount INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                result_id TEXT,
+                details TEXT,
+                PRIMARY KEY (matrix_id, cell_index)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS load_runs (
+                id TEXT PRIMARY KEY,
+                requested_by TEXT NOT NULL,
+                network TEXT NOT NULL,
+                ramp_up_secs INTEGER NOT NULL,
+                scenario TEXT,
+                skip_faucet INTEGER NOT NULL,
+                created_at TEXT NOT NULL,
+                finished_at TEXT,
+                baseline INTEGER NOT NULL DEFAULT 0,
+                report TEXT
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS load_suites (
+                load_id TEXT NOT NULL REFERENCES load_runs (id),
+                suite_index INTEGER NOT NULL,
+                status TEXT NOT NULL,
+                started_at TEXT,
+                result_id TEXT,
+                details TEXT,
+                PRIMARY KEY (load_id, suite_index)
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS result_contracts (
+                result_id TEXT NOT NULL REFERENCES test_results (id),
+                position INTEGER NOT NULL,
+                contract_id TEXT NOT NULL,
+                PRIMARY KEY (result_id, position)
+            )",
+            [],
+        )?;
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS result_contracts_contract_id
+             ON result_contracts (contract_id)",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS scenario_checkpoints (
+                run_id TEXT PRIMARY KEY,
+                scenario TEXT NOT NULL,
+                triggered_by TEXT,
+                checkpoint TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS audit_log (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                timestamp TEXT NOT NULL,
+                caller TEXT NOT NULL,
+                method TEXT NOT NULL,
+                path TEXT NOT NULL,
+                status INTEGER NOT NULL
+            )",
+            [],
+        )?;
+
+        Ok(Database {
+            conn,
+            artifacts,
+            secrets,
+        })
     }
 
     pub fn save_result(&self, result: &TestResult) -> Result<()> {
+        // Never store a passphrase in the clear
+        let passphrase_encrypted = match (&result.passphrase, &self.secrets) {
+            (Some(passphrase), Some(secrets)) => Some(secrets.encrypt(passphrase)),
+            (Some(_), None) => {
+                return Err(rusqlite::Error::ToSqlConversionFailure(
+                    "server.secret_key is required to store a BIP39 passphrase".into(),
+                ))
+            }
+            (None, _) => None,
+        };
+
+        // Logs live in the artifact store; the column is kept for older rows
+        self.artifacts
+            .write_logs(&result.id, &result.logs)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+
         self.conn.execute(
             "INSERT INTO test_results (
                 id, success, details, mnemonic, btc_address, lava_pubkey, 
-                contract_id, steps_completed, logs, timestamp
-            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                contract_id, steps_completed, logs, timestamp, cli_version, cli_sha256, error,
+                triggered_by, network, address_scheme, derivation_path, cli_btc_address,
+                address_mismatch, passphrase_encrypted, account_index, wallet_id, faucet_sats,
+                btc_balance_sats, faucet_receipts, inconclusive, scenario, scenario_source
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17,
+                ?18, ?19, ?20, ?21, ?22, ?23, ?24, ?25, ?26, ?27, ?28)",
             params![
                 result.id,
                 result.success as i32,
@@ -70,52 +404,105 @@ impl Database {
                 result.lava_pubkey,
                 result.contract_id,
                 serde_json::to_string(&result.steps_completed).unwrap(),
-                result.logs,
+                "",
                 result.timestamp.to_rfc3339(),
+                result.cli_version,
+                result.cli_sha256,
+                result
+                    .error
+                    .as_ref()
+                    .map(|error| serde_json::to_string(error).unwrap()),
+                result.triggered_by,
+                result.network.map(BtcNetwork::as_str),
+                result.address_scheme.map(AddressScheme::as_str),
+                result.derivation_path,
+                result.cli_btc_address,
+                result.address_mismatch,
+                passphrase_encrypted,
+                result.account_index,
+                result.wallet_id,
+                result.faucet_sats.map(|sats| sats as i64),
+                result.btc_balance_sats.map(|sats| sats as i64),
+                serde_json::to_string(&result.faucet_receipts).unwrap(),
+                result.inconclusive as i32,
+                result.scenario,
+                result.scenario_source,
             ],
         )?;
+        for (position, contract_id) in result.contract_ids.iter().enumerate() {
+            self.conn.execute(
+                "INSERT INTO result_contracts (result_id, position, contract_id)
+                 VALUES (?1, ?2, ?3)",
+                params![result.id, position as i64, contract_id],
+            )?;
+        }
 
         Ok(())
     }
 
-    pub fn get_all_results(&self) -> Result<Vec<TestResult>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, success, details, mnemonic, btc_address, lava_pubkey, 
-             contract_id, steps_completed, logs, timestamp 
-             FROM test_results 
-             ORDER BY timestamp DESC",
-        )?;
+    pub fn get_all_results(&self, filter: &ResultFilter) -> Result<Vec<TestResult>> {
+        let mut conditions = Vec::new();
+        let mut values: Vec<String> = Vec::new();
 
-        let rows = stmt.query_map([], |row| {
-            let steps_json: String = row.get(7)?;
-            let steps: Vec<String> = serde_json::from_str(&steps_json).map_err(|_| {
-                rusqlite::Error::InvalidColumnType(7, "Invalid JSON".to_string(), Type::Text)
-            })?;
+        if let Some(success) = filter.success {
+            conditions.push("success = ?".to_string());
+            values.push((success as i32).to_string());
+        }
+        if let Some(network) = filter.network {
+            conditions.push("network = ?".to_string());
+            values.push(network.to_string());
+        }
+        if let Some(scheme) = filter.address_scheme {
+            conditions.push("address_scheme = ?".to_string());
+            values.push(scheme.to_string());
+        }
+        if let Some(mismatch) = filter.address_mismatch {
+            conditions.push("address_mismatch = CAST(? AS INTEGER)".to_string());
+            values.push((mismatch as i32).to_string());
+        }
+        if let Some(inconclusive) = filter.inconclusive {
+            conditions.push("inconclusive = CAST(? AS INTEGER)".to_string());
+            values.push((inconclusive as i32).to_string());
+        }
+        if let Some(scenario) = &filter.scenario {
+            conditions.push("scenario = ?".to_string());
+            values.push(scenario.clone());
+        }
+        if let Some(contract_id) = &filter.contract_id {
+            conditions.push(
+                "id IN (SELECT result_id FROM result_contracts WHERE contract_id = ?)".to_string(),
+            );
+            values.push(contract_id.clone());
+        }
+        if let Some(cli_version) = &filter.cli_version {
+            conditions.push("cli_version = ?".to_string());
+            values.push(cli_version.clone());
+        }
+        if let Some(kind) = filter.error_kind {
+            conditions.push("json_extract(error, '$.kind') = ?".to_string());
+            values.push(serde_json::to_value(kind).unwrap().as_str().unwrap().to_string());
+        }
+        if let Some(step) = filter.error_step {
+            conditions.push("json_extract(error, '$.step') = CAST(? AS INTEGER)".to_string());
+            values.push(step.to_string());
+        }
+        if let Some(retryable) = filter.retryable {
+            conditions.push("json_extract(error, '$.retryable') = CAST(? AS INTEGER)".to_string());
+            values.push((retryable as i32).to_string());
+        }
 
-            let timestamp_str: String = row.get(9)?;
-            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
-                .map_err(|_| {
-                    rusqlite::Error::InvalidColumnType(
-                        9,
-                        "Invalid timestamp".to_string(),
-                        Type::Text,
-                    )
-                })?
-                .with_timezone(&Utc);
-
-            Ok(TestResult {
-                id: row.get(0)?,
-                success: row.get::<_, i32>(1)? != 0,
-                details: row.get(2)?,
-                mnemonic: row.get(3)?,
-                btc_address: row.get(4)?,
-                lava_pubkey: row.get(5)?,
-                contract_id: row.get(6)?,
-                steps_completed: steps,
-                logs: row.get(8)?,
-                timestamp,
-            })
-        })?;
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", conditions.join(" AND "))
+        };
+
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM test_results {} ORDER BY timestamp DESC",
+            RESULT_COLUMNS, where_clause
+        ))?;
+
+        let rows = stmt.query_map(params_from_iter(values.iter()), |row| self.read_result(row))?;
 
         let mut results = Vec::new();
         for row in rows {
@@ -126,98 +513,734 @@ impl Database {
     }
 
     pub fn get_result(&self, id: &str) -> Result<Option<TestResult>> {
-        let mut stmt = self.conn.prepare(
-            "SELECT id, success, details, mnemonic, btc_address, lava_pubkey, 
-             contract_id, steps_completed, logs, timestamp 
-             FROM test_results 
-             WHERE id = ?",
-        )?;
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM test_results WHERE id = ?",
+            RESULT_COLUMNS
+        ))?;
 
-        let rows = stmt.query_map([id], |row| {
-            let steps_json: String = row.get(7)?;
-            let steps: Vec<String> = serde_json::from_str(&steps_json).map_err(|_| {
-                rusqlite::Error::InvalidColumnType(7, "Invalid JSON".to_string(), Type::Text)
-            })?;
+        let mut rows = stmt.query_map([id], |row| self.read_result(row))?;
+        rows.next().transpose()
+    }
+
+    /// Returns a run's log as JSON lines, reading the artifact store first and
+    /// falling back to the `logs` column for rows saved before it existed.
+    pub fn get_logs(&self, id: &str) -> Result<Option<String>> {
+        if let Some(content) = self
+            .artifacts
+            .read_logs(id)
+            .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?
+        {
+            return Ok(Some(content));
+        }
 
-            let timestamp_str: String = row.get(9)?;
+        let mut stmt = self
+            .conn
+            .prepare("SELECT logs, timestamp FROM test_results WHERE id = ?")?;
+        let mut rows = stmt.query_map([id], |row| {
+            let logs: String = row.get(0)?;
+            let timestamp_str: String = row.get(1)?;
             let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
-                .map_err(|_| {
-                    rusqlite::Error::InvalidColumnType(
-                        9,
-                        "Invalid timestamp".to_string(),
-                        Type::Text,
-                    )
-                })?
-                .with_timezone(&Utc);
-
-            Ok(TestResult {
-                id: row.get(0)?,
-                success: row.get::<_, i32>(1)? != 0,
-                details: row.get(2)?,
-                mnemonic: row.get(3)?,
-                btc_address: row.get(4)?,
-                lava_pubkey: row.get(5)?,
-                contract_id: row.get(6)?,
-                steps_completed: steps,
-                logs: row.get(8)?,
-                timestamp,
+                .map(|t| t.with_timezone(&Utc))
+                .unwrap_or_else(|_| Utc::now());
+            Ok(events::to_json_lines(&events::from_json_lines(&logs, timestamp)))
+        })?;
+        rows.next().transpose()
+    }
+
+    pub fn get_last_successful_test(&self) -> Result<Option<TestResult>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM test_results 
+             WHERE success = 1
+             ORDER BY timestamp DESC 
+             LIMIT 1",
+            RESULT_COLUMNS
+        ))?;
+
+        let mut rows = stmt.query_map([], |row| self.read_result(row))?;
+        rows.next().transpose()
+    }
+
+    /// The most recent passing run of the same flow and network that
+    /// finished before `result`.
+    pub fn get_last_passing_before(&self, result: &TestResult) -> Result<Option<TestResult>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM test_results
+             WHERE success = 1 AND timestamp < ?1 AND scenario IS ?2 AND network IS ?3
+             ORDER BY timestamp DESC
+             LIMIT 1",
+            RESULT_COLUMNS
+        ))?;
+
+        let mut rows = stmt.query_map(
+            params![
+                result.timestamp.to_rfc3339(),
+                result.scenario,
+                result.network.map(BtcNetwork::as_str)
+            ],
+            |row| self.read_result(row),
+        )?;
+        rows.next().transpose()
+    }
+
+    pub fn record_request(&self, caller: &str, method: &str, path: &str, status: u16) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO audit_log (timestamp, caller, method, path, status)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![Utc::now().to_rfc3339(), caller, method, path, status],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_audit_log(&self, limit: u32) -> Result<Vec<AuditEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT timestamp, caller, method, path, status
+             FROM audit_log
+             ORDER BY id DESC
+             LIMIT ?",
+        )?;
+
+        let rows = stmt.query_map([limit], |row| {
+            Ok(AuditEntry {
+                timestamp: row.get(0)?,
+                caller: row.get(1)?,
+                method: row.get(2)?,
+                path: row.get(3)?,
+                status: row.get(4)?,
             })
         })?;
 
-        let mut results = Vec::new();
+        let mut entries = Vec::new();
         for row in rows {
-            results.push(row?);
+            entries.push(row?);
         }
 
-        Ok(results.into_iter().next())
+        Ok(entries)
     }
 
-    pub fn get_last_successful_test(&self) -> Result<Option<TestResult>> {
+    /// Counts runs saved since `since`, e.g. to seed the daily run budget.
+    pub fn count_results_since(&self, since: DateTime<Utc>) -> Result<u32> {
+        self.conn.query_row(
+            "SELECT COUNT(*) FROM test_results WHERE timestamp >= ?",
+            [since.to_rfc3339()],
+            |row| row.get(0),
+        )
+    }
+
+    /// Groups run outcomes by the CLI version and binary hash they ran against.
+    pub fn get_cli_version_summary(&self) -> Result<Vec<CliVersionSummary>> {
         let mut stmt = self.conn.prepare(
-            "SELECT id, success, details, mnemonic, btc_address, lava_pubkey, 
-             contract_id, steps_completed, logs, timestamp 
-             FROM test_results 
-             WHERE success = 1
-             ORDER BY timestamp DESC 
+            "SELECT cli_version, cli_sha256, COUNT(*), SUM(success), MAX(timestamp)
+             FROM test_results
+             GROUP BY cli_version, cli_sha256
+             ORDER BY MAX(timestamp) DESC",
+        )?;
+
+        let rows = stmt.query_map([], |row| {
+            let total: i64 = row.get(2)?;
+            let passed: i64 = row.get(3)?;
+            Ok(CliVersionSummary {
+                cli_version: row.get(0)?,
+                cli_sha256: row.get(1)?,
+                total,
+                passed,
+                failed: total - passed,
+                last_run: row.get(4)?,
+            })
+        })?;
+
+        let mut summaries = Vec::new();
+        for row in rows {
+            summaries.push(row?);
+        }
+
+        Ok(summaries)
+    }
+
+    pub fn add_wallet(&self, wallet: &PooledWallet) -> Result<()> {
+        self.conn.execute(
+            &format!(
+                "INSERT INTO wallet_pool ({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+                WALLET_COLUMNS
+            ),
+            params![
+                wallet.id,
+                wallet.network.as_str(),
+                wallet.mnemonic,
+                wallet.btc_address,
+                wallet.balance_sats.map(|sats| sats as i64),
+                wallet.balance_updated_at,
+                wallet.leased_at,
+                wallet.last_used_at,
+                wallet.runs,
+                wallet.created_at,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn get_wallets(&self) -> Result<Vec<PooledWallet>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM wallet_pool ORDER BY network, created_at",
+            WALLET_COLUMNS
+        ))?;
+        let rows = stmt.query_map([], row_to_wallet)?;
+
+        let mut wallets = Vec::new();
+        for row in rows {
+            wallets.push(row?);
+        }
+
+        Ok(wallets)
+    }
+
+    pub fn count_wallets(&self, network: BtcNetwork) -> Result<u32> {
+        self.conn.query_row(
+            "SELECT COUNT(*) FROM wallet_pool WHERE network = ?",
+            [network.as_str()],
+            |row| row.get(0),
+        )
+    }
+
+    /// Locks the least recently used free wallet on `network`. Leases taken
+    /// before `stale_before` are treated as abandoned and can be taken over.
+    pub fn lease_wallet(
+        &self,
+        network: BtcNetwork,
+        stale_before: DateTime<Utc>,
+    ) -> Result<Option<PooledWallet>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT {} FROM wallet_pool
+             WHERE network = ?1 AND (leased_at IS NULL OR leased_at < ?2)
+             ORDER BY last_used_at IS NOT NULL, last_used_at
              LIMIT 1",
+            WALLET_COLUMNS
+        ))?;
+        let mut rows = stmt.query_map(
+            params![network.as_str(), stale_before.to_rfc3339()],
+            row_to_wallet,
+        )?;
+        let mut wallet = match rows.next().transpose()? {
+            Some(wallet) => wallet,
+            None => return Ok(None),
+        };
+
+        let now = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "UPDATE wallet_pool SET leased_at = ? WHERE id = ?",
+            params![now, wallet.id],
+        )?;
+        wallet.leased_at = Some(now);
+        Ok(Some(wallet))
+    }
+
+    /// Unlocks a leased wallet, recording the run and its balance. An unknown
+    /// balance is cleared so the next lease tops the wallet up.
+    pub fn release_wallet(&self, id: &str, balance_sats: Option<u64>) -> Result<()> {
+        let now = Utc::now().to_rfc3339();
+        self.conn.execute(
+            "UPDATE wallet_pool SET leased_at = NULL, last_used_at = ?1, runs = runs + 1,
+                 balance_sats = ?2, balance_updated_at = ?3
+             WHERE id = ?4",
+            params![
+                now,
+                balance_sats.map(|sats| sats as i64),
+                balance_sats.map(|_| now.clone()),
+                id
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn delete_wallet(&self, id: &str) -> Result<bool> {
+        let deleted = self
+            .conn
+            .execute("DELETE FROM wallet_pool WHERE id = ?", [id])?;
+        Ok(deleted > 0)
+    }
+
+    pub fn create_matrix_run(&self, run: &MatrixRun) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO matrix_runs (
+                id, requested_by, network, concurrency, skip_faucet, created_at, finished_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                run.id,
+                run.requested_by,
+                run.network.as_str(),
+                run.concurrency,
+                run.skip_faucet as i32,
+                run.created_at,
+                run.finished_at,
+            ],
+        )?;
+        for cell in &run.cells {
+            self.conn.execute(
+                "INSERT INTO matrix_cells (
+                    matrix_id, cell_index, ltv_ratio_bp, duration_days, amount, status,
+                    result_id, details
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    run.id,
+                    cell.index,
+                    cell.ltv_ratio_bp,
+                    cell.duration_days,
+                    cell.amount as i64,
+                    cell.status.as_str(),
+                    cell.result_id,
+                    cell.details,
+                ],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn update_matrix_cell(
+        &self,
+        matrix_id: &str,
+        index: u32,
+        status: CellStatus,
+        result_id: Option<&str>,
+        details: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE matrix_cells SET status = ?1, result_id = ?2, details = ?3
+             WHERE matrix_id = ?4 AND cell_index = ?5",
+            params![status.as_str(), result_id, details, matrix_id, index],
+        )?;
+        Ok(())
+    }
+
+    pub fn finish_matrix_run(&self, id: &str) -> Result<()> {
+        self.conn.execute(
+            "UPDATE matrix_runs SET finished_at = ?1 WHERE id = ?2",
+            params![Utc::now().to_rfc3339(), id],
+        )?;
+        Ok(())
+    }
+
+    /// Marks cells left pending or running by a previous process as skipped
+    /// and closes their runs; returns how many runs were interrupted.
+    pub fn interrupt_matrix_runs(&self) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE matrix_cells SET status = 'skipped', details = 'Interrupted by a server restart'
+             WHERE status IN ('pending', 'running')",
+            [],
+        )?;
+        self.conn.execute(
+            "UPDATE matrix_runs SET finished_at = ?1 WHERE finished_at IS NULL",
+            params![Utc::now().to_rfc3339()],
+        )
+    }
+
+    pub fn create_load_run(&self, run: &LoadRun) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO load_runs (
+                id, requested_by, network, ramp_up_secs, scenario, skip_faucet, created_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                run.id,
+                run.requested_by,
+                run.network.as_str(),
+                run.ramp_up_secs as i64,
+                run.scenario,
+                run.skip_faucet as i32,
+                run.created_at,
+            ],
+        )?;
+        for suite in &run.suites {
+            self.conn.execute(
+                "INSERT INTO load_suites (load_id, suite_index, status) VALUES (?1, ?2, ?3)",
+                params![run.id, suite.index, suite.status.as_str()],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn start_load_suite(&self, load_id: &str, index: u32) -> Result<()> {
+        self.conn.execute(
+            "UPDATE load_suites SET status = 'running', started_at = ?1
+             WHERE load_id = ?2 AND suite_index = ?3",
+            params![Utc::now().to_rfc3339(), load_id, index],
+        )?;
+        Ok(())
+    }
+
+    pub fn update_load_suite(
+        &self,
+        load_id: &str,
+        index: u32,
+        status: CellStatus,
+        result_id: Option<&str>,
+        details: Option<&str>,
+    ) -> Result<()> {
+        self.conn.execute(
+            "UPDATE load_suites SET status = ?1, result_id = ?2, details = ?3
+             WHERE load_id = ?4 AND suite_index = ?5",
+            params![status.as_str(), result_id, details, load_id, index],
         )?;
+        Ok(())
+    }
+
+    pub fn finish_load_run(&self, id: &str, report: &LoadReport) -> Result<()> {
+        self.conn.execute(
+            "UPDATE load_runs SET finished_at = ?1, report = ?2 WHERE id = ?3",
+            params![Utc::now().to_rfc3339(), serde_json::to_string(report).unwrap(), id],
+        )?;
+        Ok(())
+    }
+
+    /// Like `interrupt_matrix_runs`. Interrupted load runs get no report, as
+    /// their latencies would be incomplete.
+    pub fn interrupt_load_runs(&self) -> Result<usize> {
+        self.conn.execute(
+            "UPDATE load_suites SET status = 'skipped', details = 'Interrupted by a server restart'
+             WHERE status IN ('pending', 'running')",
+            [],
+        )?;
+        self.conn.execute(
+            "UPDATE load_runs SET finished_at = ?1 WHERE finished_at IS NULL",
+            params![Utc::now().to_rfc3339()],
+        )
+    }
+
+    /// Makes `id` the load run later runs compare with; returns false if
+    /// there is no finished run with that id.
+    pub fn set_load_baseline(&self, id: &str) -> Result<bool> {
+        let exists: bool = self.conn.query_row(
+            "SELECT EXISTS (SELECT 1 FROM load_runs WHERE id = ? AND report IS NOT NULL)",
+            [id],
+            |row| row.get(0),
+        )?;
+        if exists {
+            self.conn.execute("UPDATE load_runs SET baseline = (id = ?)", [id])?;
+        }
+        Ok(exists)
+    }
+
+    pub fn get_load_baseline(&self) -> Result<Option<LoadRun>> {
+        self.query_load_runs("WHERE baseline = 1", &[])
+            .map(|runs| runs.into_iter().next())
+    }
 
+    pub fn get_load_runs(&self) -> Result<Vec<LoadRun>> {
+        self.query_load_runs("", &[])
+    }
+
+    pub fn get_load_run(&self, id: &str) -> Result<Option<LoadRun>> {
+        self.query_load_runs("WHERE id = ?", &[id])
+            .map(|runs| runs.into_iter().next())
+    }
+
+    fn query_load_runs(&self, where_clause: &str, values: &[&str]) -> Result<Vec<LoadRun>> {
+        let mut stmt = self.conn.prepare(&format!(
+            "SELECT id, requested_by, network, ramp_up_secs, scenario, skip_faucet, created_at,
+                    finished_at, baseline, report
+             FROM load_runs {} ORDER BY created_at DESC",
+            where_clause
+        ))?;
+        let rows = stmt.query_map(params_from_iter(values.iter()), row_to_load_run)?;
+
+        let mut runs = Vec::new();
+        for row in rows {
+            let mut run = row?;
+            run.suites = self.get_load_suites(&run.id)?;
+            runs.push(run);
+        }
+        Ok(runs)
+    }
+
+    fn get_load_suites(&self, load_id: &str) -> Result<Vec<LoadSuite>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT suite_index, status, started_at, result_id, details
+             FROM load_suites WHERE load_id = ? ORDER BY suite_index",
+        )?;
+        let rows = stmt.query_map([load_id], |row| {
+            Ok(LoadSuite {
+                index: row.get(0)?,
+                status: row.get::<_, String>(1)?.parse().map_err(|_| {
+                    rusqlite::Error::InvalidColumnType(1, "Invalid suite status".to_string(), Type::Text)
+                })?,
+                started_at: row.get(2)?,
+                result_id: row.get(3)?,
+                details: row.get(4)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// Stores the latest checkpoint of a resumable scenario run.
+    pub fn save_scenario_checkpoint(
+        &self,
+        checkpoint: &ScenarioCheckpoint,
+        triggered_by: &str,
+    ) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO scenario_checkpoints (
+                run_id, scenario, triggered_by, checkpoint, updated_at
+            ) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                checkpoint.run_id,
+                checkpoint.scenario,
+                triggered_by,
+                serde_json::to_string(checkpoint).unwrap(),
+                checkpoint.updated_at.to_rfc3339(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Called once the run's result is saved.
+    pub fn delete_scenario_checkpoint(&self, run_id: &str) -> Result<()> {
+        self.conn.execute(
+            "DELETE FROM scenario_checkpoints WHERE run_id = ?",
+            [run_id],
+        )?;
+        Ok(())
+    }
+
+    /// Checkpoints of resumable runs that haven't finished, least recently
+    /// checkpointed first.
+    pub fn get_scenario_checkpoints(&self) -> Result<Vec<ScenarioCheckpoint>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT checkpoint, triggered_by FROM scenario_checkpoints ORDER BY rowid",
+        )?;
         let rows = stmt.query_map([], |row| {
-            let steps_json: String = row.get(7)?;
-            let steps: Vec<String> = serde_json::from_str(&steps_json).map_err(|_| {
-                rusqlite::Error::InvalidColumnType(7, "Invalid JSON".to_string(), Type::Text)
-            })?;
+            let mut checkpoint: ScenarioCheckpoint =
+                serde_json::from_str(&row.get::<_, String>(0)?).map_err(|_| {
+                    rusqlite::Error::InvalidColumnType(0, "Invalid JSON".to_string(), Type::Text)
+                })?;
+            checkpoint.triggered_by = row.get(1)?;
+            Ok(checkpoint)
+        })?;
+        rows.collect()
+    }
 
-            let timestamp_str: String = row.get(9)?;
-            let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
-                .map_err(|_| {
-                    rusqlite::Error::InvalidColumnType(
-                        9,
-                        "Invalid timestamp".to_string(),
-                        Type::Text,
-                    )
-                })?
-                .with_timezone(&Utc);
-
-            Ok(TestResult {
-                id: row.get(0)?,
-                success: row.get::<_, i32>(1)? != 0,
-                details: row.get(2)?,
-                mnemonic: row.get(3)?,
-                btc_address: row.get(4)?,
-                lava_pubkey: row.get(5)?,
-                contract_id: row.get(6)?,
-                steps_completed: steps,
-                logs: row.get(8)?,
-                timestamp,
+    pub fn get_matrix_runs(&self) -> Result<Vec<MatrixRun>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, requested_by, network, concurrency, skip_faucet, created_at, finished_at
+             FROM matrix_runs ORDER BY created_at DESC",
+        )?;
+        let rows = stmt.query_map([], row_to_matrix_run)?;
+
+        let mut runs = Vec::new();
+        for row in rows {
+            let mut run = row?;
+            run.cells = self.get_matrix_cells(&run.id)?;
+            run.summarize();
+            runs.push(run);
+        }
+
+        Ok(runs)
+    }
+
+    pub fn get_matrix_run(&self, id: &str) -> Result<Option<MatrixRun>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT id, requested_by, network, concurrency, skip_faucet, created_at, finished_at
+             FROM matrix_runs WHERE id = ?",
+        )?;
+        let mut rows = stmt.query_map([id], row_to_matrix_run)?;
+        match rows.next().transpose()? {
+            Some(mut run) => {
+                run.cells = self.get_matrix_cells(&run.id)?;
+                run.summarize();
+                Ok(Some(run))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn get_matrix_cells(&self, matrix_id: &str) -> Result<Vec<MatrixCell>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT cell_index, ltv_ratio_bp, duration_days, amount, status, result_id, details
+             FROM matrix_cells WHERE matrix_id = ? ORDER BY cell_index",
+        )?;
+        let rows = stmt.query_map([matrix_id], |row| {
+            Ok(MatrixCell {
+                index: row.get(0)?,
+                ltv_ratio_bp: row.get(1)?,
+                duration_days: row.get(2)?,
+                amount: row.get::<_, i64>(3)? as u64,
+                status: row.get::<_, String>(4)?.parse().map_err(|_| {
+                    rusqlite::Error::InvalidColumnType(4, "Invalid cell status".to_string(), Type::Text)
+                })?,
+                result_id: row.get(5)?,
+                details: row.get(6)?,
             })
         })?;
 
-        let mut results = Vec::new();
+        let mut cells = Vec::new();
         for row in rows {
-            results.push(row?);
+            cells.push(row?);
+        }
+
+        Ok(cells)
+    }
+
+    /// Contract ids a run created, in order.
+    fn get_result_contracts(&self, result_id: &str) -> Result<Vec<String>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT contract_id FROM result_contracts WHERE result_id = ? ORDER BY position",
+        )?;
+        let rows = stmt.query_map([result_id], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// Maps a `RESULT_COLUMNS` row, decrypting the stored passphrase. One that
+    /// can't be decrypted is shown masked rather than dropped.
+    fn read_result(&self, row: &Row) -> Result<TestResult> {
+        let mut result = row_to_result(row)?;
+        result.contract_ids = self.get_result_contracts(&result.id)?;
+        // Rows saved before runs could create several contracts
+        if result.contract_ids.is_empty() {
+            result.contract_ids.extend(result.contract_id.clone());
         }
+        if let Some(sealed) = result.passphrase.take() {
+            let passphrase = match self.secrets.as_ref().map(|secrets| secrets.decrypt(&sealed)) {
+                Some(Ok(passphrase)) => passphrase,
+                Some(Err(e)) => {
+                    log::warn!("Cannot decrypt passphrase of result {}: {}", result.id, e);
+                    "***".to_string()
+                }
+                None => "***".to_string(),
+            };
+            result.passphrase = Some(passphrase);
+        }
+        Ok(result)
+    }
+}
 
-        Ok(results.into_iter().next())
+fn add_column_if_missing(conn: &Connection, table: &str, column: &str, decl: &str) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+    let exists = stmt
+        .query_map([], |row| row.get::<_, String>(1))?
+        .filter_map(|name| name.ok())
+        .any(|name| name == column);
+
+    if !exists {
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, decl),
+            [],
+        )?;
     }
+
+    Ok(())
+}
+
+fn row_to_wallet(row: &Row) -> Result<PooledWallet> {
+    let network: String = row.get(1)?;
+    Ok(PooledWallet {
+        id: row.get(0)?,
+        network: network.parse().map_err(|_| {
+            rusqlite::Error::InvalidColumnType(1, "Invalid network".to_string(), Type::Text)
+        })?,
+        mnemonic: row.get(2)?,
+        btc_address: row.get(3)?,
+        balance_sats: row.get::<_, Option<i64>>(4)?.map(|sats| sats as u64),
+        balance_updated_at: row.get(5)?,
+        leased_at: row.get(6)?,
+        last_used_at: row.get(7)?,
+        runs: row.get(8)?,
+        created_at: row.get(9)?,
+    })
+}
+
+fn row_to_result(row: &Row) -> Result<TestResult> {
+    let steps_json: String = row.get(7)?;
+    let steps: Vec<String> = serde_json::from_str(&steps_json).map_err(|_| {
+        rusqlite::Error::InvalidColumnType(7, "Invalid JSON".to_string(), Type::Text)
+    })?;
+
+    let error = match row.get::<_, Option<String>>(11)? {
+        Some(json) => Some(serde_json::from_str(&json).map_err(|_| {
+            rusqlite::Error::InvalidColumnType(11, "Invalid JSON".to_string(), Type::Text)
+        })?),
+        None => None,
+    };
+
+    let timestamp_str: String = row.get(8)?;
+    let timestamp = DateTime::parse_from_rfc3339(&timestamp_str)
+        .map_err(|_| {
+            rusqlite::Error::InvalidColumnType(8, "Invalid timestamp".to_string(), Type::Text)
+        })?
+        .with_timezone(&Utc);
+
+    Ok(TestResult {
+        id: row.get(0)?,
+        success: row.get::<_, i32>(1)? != 0,
+        details: row.get(2)?,
+        mnemonic: row.get(3)?,
+        // Still encrypted; see `Database::read_result`
+        passphrase: row.get(18)?,
+        account_index: row.get(19)?,
+        wallet_id: row.get(20)?,
+        faucet_sats: row.get::<_, Option<i64>>(21)?.map(|sats| sats as u64),
+        btc_balance_sats: row.get::<_, Option<i64>>(22)?.map(|sats| sats as u64),
+        faucet_receipts: row
+            .get::<_, Option<String>>(23)?
+            .and_then(|receipts| serde_json::from_str(&receipts).ok())
+            .unwrap_or_default(),
+        inconclusive: row.get::<_, i32>(24)? != 0,
+        scenario: row.get(25)?,
+        scenario_source: row.get(26)?,
+        network: row
+            .get::<_, Option<String>>(13)?
+            .and_then(|network| network.parse().ok()),
+        btc_address: row.get(4)?,
+        address_scheme: row
+            .get::<_, Option<String>>(14)?
+            .and_then(|scheme| scheme.parse().ok()),
+        derivation_path: row.get(15)?,
+        cli_btc_address: row.get(16)?,
+        address_mismatch: row.get(17)?,
+        lava_pubkey: row.get(5)?,
+        contract_id: row.get(6)?,
+        contract_ids: Vec::new(),
+        steps_completed: steps,
+        logs: Vec::new(),
+        timestamp,
+        cli_version: row.get(9)?,
+        cli_sha256: row.get(10)?,
+        error,
+        triggered_by: row.get(12)?,
+    })
+}
+
+fn row_to_matrix_run(row: &Row) -> Result<MatrixRun> {
+    Ok(MatrixRun {
+        id: row.get(0)?,
+        requested_by: row.get(1)?,
+        network: row.get::<_, String>(2)?.parse().map_err(|_| {
+            rusqlite::Error::InvalidColumnType(2, "Invalid network".to_string(), Type::Text)
+        })?,
+        concurrency: row.get(3)?,
+        skip_faucet: row.get::<_, i32>(4)? != 0,
+        created_at: row.get(5)?,
+        finished_at: row.get(6)?,
+        summary: MatrixSummary::default(),
+        cells: Vec::new(),
+    })
+}
+
+fn row_to_load_run(row: &Row) -> Result<LoadRun> {
+    let report = match row.get::<_, Option<String>>(9)? {
+        Some(json) => Some(serde_json::from_str(&json).map_err(|_| {
+            rusqlite::Error::InvalidColumnType(9, "Invalid JSON".to_string(), Type::Text)
+        })?),
+        None => None,
+    };
+    Ok(LoadRun {
+        id: row.get(0)?,
+        requested_by: row.get(1)?,
+        network: row.get::<_, String>(2)?.parse().map_err(|_| {
+            rusqlite::Error::InvalidColumnType(2, "Invalid network".to_string(), Type::Text)
+        })?,
+        ramp_up_secs: row.get::<_, i64>(3)? as u64,
+        scenario: row.get(4)?,
+        skip_faucet: row.get::<_, i32>(5)? != 0,
+        created_at: row.get(6)?,
+        finished_at: row.get(7)?,
+        baseline: row.get::<_, i32>(8)? != 0,
+        suites: Vec::new(),
+        report,
+    })
 }
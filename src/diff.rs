@@ -0,0 +1,414 @@
+use crate::db::TestResult;
+use crate::events::{self, LogEvent};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::OnceLock;
+use utoipa::ToSchema;
+
+/// Unchanged lines kept around each change in an output diff.
+const CONTEXT_LINES: usize = 3;
+
+/// Beyond this many line pairs an output is shown as fully replaced rather
+/// than diffed line by line.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StepOutcome {
+    Passed,
+    Failed,
+    /// The run stopped or skipped ahead before the step
+    NotRun,
+}
+
+/// A run option or CLI property that differs between the two runs.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FieldChange {
+    pub field: String,
+    pub a: Option<String>,
+    pub b: Option<String>,
+}
+
+/// Normalized diff of one output stream of a step, in unified style: lines
+/// start with `-` (only in a), `+` (only in b) or a space, and `@@` lines
+/// stand for unchanged lines left out.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StreamDiff {
+    /// `command`, `stdout` or `stderr`
+    pub stream: String,
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StepDiff {
+    pub step: u32,
+    pub name: String,
+    pub a: StepOutcome,
+    pub b: StepOutcome,
+    /// The outcome or the normalized CLI output differs
+    pub diverged: bool,
+    pub a_duration_ms: Option<i64>,
+    pub b_duration_ms: Option<i64>,
+    pub duration_change_ms: Option<i64>,
+    pub duration_change_pct: Option<f64>,
+    /// Only the streams that differ after normalization
+    pub output: Vec<StreamDiff>,
+}
+
+/// The compared run, in brief.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RunSide {
+    pub id: String,
+    pub success: bool,
+    pub details: String,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct RunDiff {
+    pub a: RunSide,
+    pub b: RunSide,
+    /// CLI version and run options that differ
+    pub changes: Vec<FieldChange>,
+    /// First step whose outcome or output differs
+    pub first_divergence: Option<u32>,
+    pub steps: Vec<StepDiff>,
+}
+
+/// Compares two runs step by step, from their results and log events.
+pub fn diff(a: &TestResult, a_events: &[LogEvent], b: &TestResult, b_events: &[LogEvent]) -> RunDiff {
+    let a_steps = StepRecord::collect(a, a_events);
+    let b_steps = StepRecord::collect(b, b_events);
+    let a_durations = events::step_durations(a_events);
+    let b_durations = events::step_durations(b_events);
+
+    let numbers: BTreeSet<u32> = a_steps.keys().chain(b_steps.keys()).copied().collect();
+    let steps: Vec<StepDiff> = numbers
+        .into_iter()
+        .map(|step| {
+            let empty = StepRecord::default();
+            let a_record = a_steps.get(&step).unwrap_or(&empty);
+            let b_record = b_steps.get(&step).unwrap_or(&empty);
+            let a_outcome = a_record.outcome(a, step);
+            let b_outcome = b_record.outcome(b, step);
+
+            let output: Vec<StreamDiff> = ["command", "stdout", "stderr"]
+                .into_iter()
+                .filter_map(|stream| {
+                    let a_text = normalize(&a_record.stream(stream), a);
+                    let b_text = normalize(&b_record.stream(stream), b);
+                    (a_text != b_text).then(|| StreamDiff {
+                        stream: stream.to_string(),
+                        lines: diff_lines(&a_text, &b_text),
+                    })
+                })
+                .collect();
+
+            let a_ms = a_durations.get(&step).copied();
+            let b_ms = b_durations.get(&step).copied();
+            let change_ms = a_ms.zip(b_ms).map(|(a_ms, b_ms)| b_ms - a_ms);
+            StepDiff {
+                step,
+                name: b_record.name.clone().or_else(|| a_record.name.clone()).unwrap_or_default(),
+                a: a_outcome,
+                b: b_outcome,
+                diverged: a_outcome != b_outcome || !output.is_empty(),
+                a_duration_ms: a_ms,
+                b_duration_ms: b_ms,
+                duration_change_ms: change_ms,
+                duration_change_pct: a_ms
+                    .zip(change_ms)
+                    .filter(|(a_ms, _)| *a_ms > 0)
+                    .map(|(a_ms, change)| change as f64 / a_ms as f64 * 100.0),
+                output,
+            }
+        })
+        .collect();
+
+    RunDiff {
+        a: RunSide::of(a),
+        b: RunSide::of(b),
+        changes: field_changes(a, b),
+        first_divergence: steps.iter().find(|step| step.diverged).map(|step| step.step),
+        steps,
+    }
+}
+
+impl RunSide {
+    fn of(result: &TestResult) -> Self {
+        RunSide {
+            id: result.id.clone(),
+            success: result.success,
+            details: result.details.clone(),
+            timestamp: result.timestamp.to_rfc3339(),
+        }
+    }
+}
+
+fn field_changes(a: &TestResult, b: &TestResult) -> Vec<FieldChange> {
+    let fields = |result: &TestResult| -> Vec<(&'static str, Option<String>)> {
+        vec![
+            ("cli_version", result.cli_version.clone()),
+            ("cli_sha256", result.cli_sha256.clone()),
+            ("network", result.network.map(|network| network.to_string())),
+            ("scenario", result.scenario.clone()),
+            ("address_scheme", result.address_scheme.map(|scheme| scheme.to_string())),
+            ("derivation_path", result.derivation_path.clone()),
+            ("account_index", result.account_index.map(|index| index.to_string())),
+            ("passphrase", result.passphrase.as_ref().map(|_| "set".to_string())),
+            ("pooled_wallet", Some(result.wallet_id.is_some().to_string())),
+            ("faucet_sats", result.faucet_sats.map(|sats| sats.to_string())),
+            ("triggered_by", result.triggered_by.clone()),
+        ]
+    };
+    fields(a)
+        .into_iter()
+        .zip(fields(b))
+        .filter(|((_, a), (_, b))| a != b)
+        .map(|((field, a), (_, b))| FieldChange {
+            field: field.to_string(),
+            a,
+            b,
+        })
+        .collect()
+}
+
+/// What one run logged for one step.
+#[derive(Default)]
+struct StepRecord {
+    name: Option<String>,
+    completed: bool,
+    ran: bool,
+    streams: BTreeMap<&'static str, Vec<String>>,
+}
+
+impl StepRecord {
+    fn collect(result: &TestResult, events: &[LogEvent]) -> BTreeMap<u32, StepRecord> {
+        let mut steps: BTreeMap<u32, StepRecord> = BTreeMap::new();
+        for label in &result.steps_completed {
+            if let Some((step, name)) = events::parse_step_label(label) {
+                let record = steps.entry(step).or_default();
+                record.completed = true;
+                record.name.get_or_insert(name);
+            }
+        }
+        for event in events {
+            let Some(step) = event.step else { continue };
+            let record = steps.entry(step).or_default();
+            record.ran = true;
+            if let Some((_, name)) = events::parse_step_label(&event.message) {
+                record.name.get_or_insert(name);
+            }
+            if let Some(command) = event.fields.get("command").and_then(|command| command.as_str()) {
+                record.streams.entry("command").or_default().push(command.to_string());
+            }
+            match event.fields.get("stream").and_then(|stream| stream.as_str()) {
+                Some("stdout") => record.streams.entry("stdout").or_default().push(stream_text(&event.message, "stdout")),
+                Some("stderr") => record.streams.entry("stderr").or_default().push(stream_text(&event.message, "stderr")),
+                _ => {}
+            }
+        }
+        steps
+    }
+
+    fn outcome(&self, result: &TestResult, step: u32) -> StepOutcome {
+        let failed_here = !result.success
+            && result.error.as_ref().and_then(|error| error.step) == Some(step);
+        if failed_here {
+            StepOutcome::Failed
+        } else if self.completed || self.ran {
+            StepOutcome::Passed
+        } else {
+            StepOutcome::NotRun
+        }
+    }
+
+    fn stream(&self, stream: &str) -> String {
+        self.streams.get(stream).map(|texts| texts.join("\n")).unwrap_or_default()
+    }
+}
+
+/// Output events read "<label> stdout: <text>"; keeps the text.
+fn stream_text(message: &str, stream: &str) -> String {
+    let marker = format!(" {}: ", stream);
+    message
+        .split_once(&marker)
+        .map_or(message, |(_, text)| text)
+        .to_string()
+}
+
+/// Masks what differs from run to run even when nothing went differently:
+/// the run's own wallet, contracts and paths, then anything that looks like a
+/// timestamp, id, address or transaction hash.
+pub fn normalize(text: &str, result: &TestResult) -> String {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    let patterns = PATTERNS.get_or_init(|| {
+        [
+            (r"\d{4}-\d{2}-\d{2}[T ]\d{2}:\d{2}:\d{2}(\.\d+)?(Z|[+-]\d{2}:?\d{2})?", "<timestamp>"),
+            (r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}", "<uuid>"),
+            (r"\b(bc1|tb1|bcrt1)[02-9ac-hj-np-z]{8,87}\b", "<address>"),
+            (r"\b[0-9a-fA-F]{64}\b", "<hash>"),
+            (r"\b[0-9a-fA-F]{16,}\b", "<hex>"),
+            (r"\b[1-9A-HJ-NP-Za-km-z]{32,44}\b", "<address>"),
+        ]
+        .into_iter()
+        .map(|(pattern, mask)| (Regex::new(pattern).unwrap(), mask))
+        .collect()
+    });
+
+    let mut text = text.to_string();
+    let known = [
+        (Some(result.mnemonic.as_str()), "<mnemonic>"),
+        (Some(result.id.as_str()), "<run_id>"),
+        (Some(result.btc_address.as_str()), "<address>"),
+        (result.cli_btc_address.as_deref(), "<address>"),
+        (Some(result.lava_pubkey.as_str()), "<address>"),
+    ];
+    for (value, mask) in known.into_iter().filter_map(|(value, mask)| Some((value?, mask))) {
+        if !value.is_empty() && value != "***" {
+            text = text.replace(value, mask);
+        }
+    }
+    for contract_id in result.contract_ids.iter().chain(&result.contract_id) {
+        text = text.replace(contract_id.as_str(), "<contract_id>");
+    }
+    for (re, mask) in patterns {
+        text = re.replace_all(&text, *mask).into_owned();
+    }
+    text
+}
+
+/// Line diff of `a` and `b` in unified style, keeping `CONTEXT_LINES` of
+/// unchanged lines around each change.
+fn diff_lines(a: &str, b: &str) -> Vec<String> {
+    let a: Vec<&str> = a.lines().collect();
+    let b: Vec<&str> = b.lines().collect();
+    let ops = if a.len() * b.len() > MAX_DIFF_CELLS {
+        a.iter()
+            .map(|line| ('-', *line))
+            .chain(b.iter().map(|line| ('+', *line)))
+            .collect()
+    } else {
+        lcs_ops(&a, &b)
+    };
+
+    let mut lines = Vec::new();
+    let mut index = 0;
+    while index < ops.len() {
+        if ops[index].0 != ' ' {
+            lines.push(format!("{}{}", ops[index].0, ops[index].1));
+            index += 1;
+            continue;
+        }
+        let end = ops[index..]
+            .iter()
+            .position(|(op, _)| *op != ' ')
+            .map_or(ops.len(), |offset| index + offset);
+        let keep_before = if index == 0 { 0 } else { CONTEXT_LINES };
+        let keep_after = if end == ops.len() { 0 } else { CONTEXT_LINES };
+        if end - index > keep_before + keep_after {
+            lines.extend(ops[index..index + keep_before].iter().map(|(_, line)| format!(" {}", line)));
+            lines.push(format!("@@ {} unchanged lines @@", end - index - keep_before - keep_after));
+            lines.extend(ops[end - keep_after..end].iter().map(|(_, line)| format!(" {}", line)));
+        } else {
+            lines.extend(ops[index..end].iter().map(|(_, line)| format!(" {}", line)));
+        }
+        index = end;
+    }
+    lines
+}
+
+/// Edit script from a longest-common-subsequence table.
+fn lcs_ops<'a>(a: &[&'a str], b: &[&'a str]) -> Vec<(char, &'a str)> {
+    let mut table = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            table[i][j] = if a[i] == b[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+
+    let (mut i, mut j) = (0, 0);
+    let mut ops = Vec::new();
+    while i < a.len() && j < b.len() {
+        if a[i] == b[j] {
+            ops.push((' ', a[i]));
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            ops.push(('-', a[i]));
+            i += 1;
+        } else {
+            ops.push(('+', b[j]));
+            j += 1;
+        }
+    }
+    ops.extend(a[i..].iter().map(|line| ('-', *line)));
+    ops.extend(b[j..].iter().map(|line| ('+', *line)));
+    ops
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_masks_run_values_and_volatile_tokens() {
+        let mut result = TestResult::empty("9b2f4c1e-0a57-4a43-8f9e-2d1c3b4a5e6f");
+        result.btc_address = "tb1qexampleaddressxyz".to_string();
+        result.contract_ids = vec!["C42".to_string()];
+        let text = format!(
+            "run {} sent to {} for contract C42 at 2026-10-18T12:00:00.123Z in {}",
+            result.id,
+            result.btc_address,
+            "ab".repeat(32)
+        );
+        assert_eq!(
+            normalize(&text, &result),
+            "run <run_id> sent to <address> for contract <contract_id> at <timestamp> in <hash>"
+        );
+    }
+
+    #[test]
+    fn normalize_leaves_masked_mnemonics_alone() {
+        let mut result = TestResult::empty("run");
+        result.mnemonic = "***".to_string();
+        assert_eq!(normalize("MNEMONIC=\"***\"", &result), "MNEMONIC=\"***\"");
+    }
+
+    #[test]
+    fn long_unchanged_runs_collapse_to_context() {
+        let a: Vec<String> = (0..10).map(|line| format!("line {}", line)).collect();
+        let mut b = a.clone();
+        b[5] = "changed".to_string();
+        assert_eq!(
+            diff_lines(&a.join("\n"), &b.join("\n")),
+            [
+                "@@ 2 unchanged lines @@",
+                " line 2",
+                " line 3",
+                " line 4",
+                "-line 5",
+                "+changed",
+                " line 6",
+                " line 7",
+                " line 8",
+                "@@ 1 unchanged lines @@",
+            ]
+        );
+    }
+
+    #[test]
+    fn short_unchanged_runs_are_kept() {
+        assert_eq!(diff_lines("a\nb\nc", "a\nc\nd"), [" a", "-b", " c", "+d"]);
+    }
+
+    #[test]
+    fn identical_streams_have_no_changes() {
+        assert_eq!(diff_lines("a\nb", "a\nb"), ["@@ 2 unchanged lines @@"]);
+    }
+}
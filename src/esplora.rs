@@ -0,0 +1,294 @@
+use crate::test_suite::TestError;
+use actix_web::dev::Server;
+use actix_web::{web, App, HttpResponse, HttpServer, Responder};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::TcpListener;
+use std::sync::Mutex;
+
+/// Funded and spent totals for one side (chain or mempool) of an Esplora
+/// address response.
+#[derive(Debug, Deserialize)]
+struct TxoStats {
+    funded_txo_sum: u64,
+    spent_txo_sum: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct AddressStats {
+    chain_stats: TxoStats,
+    mempool_stats: TxoStats,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct AddressBalance {
+    pub confirmed_sats: u64,
+    /// Net change from mempool transactions; negative while spending
+    pub unconfirmed_sats: i64,
+}
+
+impl AddressBalance {
+    pub fn total_sats(&self) -> u64 {
+        (self.confirmed_sats as i64 + self.unconfirmed_sats).max(0) as u64
+    }
+}
+
+/// Esplora's `/tx/{txid}/status`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TxStatus {
+    pub confirmed: bool,
+    pub block_height: Option<u64>,
+    /// Block timestamp, in seconds since the epoch
+    pub block_time: Option<i64>,
+}
+
+/// Minimal client for an Esplora-compatible REST API
+/// (blockstream.info, mempool.space, mutinynet.com/api, ...).
+pub struct EsploraClient {
+    base_url: String,
+    client: Client,
+}
+
+impl EsploraClient {
+    pub fn new(base_url: &str) -> Self {
+        EsploraClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            client: Client::new(),
+        }
+    }
+
+    pub async fn address_balance(&self, address: &str) -> Result<AddressBalance, TestError> {
+        let url = format!("{}/address/{}", self.base_url, address);
+        let response = self.client.get(&url).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(TestError::network(format!("Esplora request to {} failed", url))
+                .with_http_status(status.as_u16()));
+        }
+        let stats: AddressStats = response.json().await.map_err(|e| {
+            TestError::parsing(format!("Unexpected Esplora address response: {}", e))
+        })?;
+
+        Ok(AddressBalance {
+            confirmed_sats: stats
+                .chain_stats
+                .funded_txo_sum
+                .saturating_sub(stats.chain_stats.spent_txo_sum),
+            unconfirmed_sats: stats.mempool_stats.funded_txo_sum as i64
+                - stats.mempool_stats.spent_txo_sum as i64,
+        })
+    }
+
+    /// Returns `None` while the explorer hasn't seen the transaction.
+    pub async fn tx_status(&self, txid: &str) -> Result<Option<TxStatus>, TestError> {
+        let url = format!("{}/tx/{}/status", self.base_url, txid);
+        let response = self.client.get(&url).send().await?;
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        if !status.is_success() {
+            return Err(TestError::network(format!("Esplora request to {} failed", url))
+                .with_http_status(status.as_u16()));
+        }
+        let tx_status = response.json().await.map_err(|e| {
+            TestError::parsing(format!("Unexpected Esplora transaction status: {}", e))
+        })?;
+        Ok(Some(tx_status))
+    }
+
+    pub async fn tip_height(&self) -> Result<u64, TestError> {
+        let url = format!("{}/blocks/tip/height", self.base_url);
+        let response = self.client.get(&url).send().await?;
+        let status = response.status();
+        if !status.is_success() {
+            return Err(TestError::network(format!("Esplora request to {} failed", url))
+                .with_http_status(status.as_u16()));
+        }
+        let body = response.text().await?;
+        body.trim().parse().map_err(|_| {
+            TestError::parsing(format!("Unexpected Esplora tip height: {}", body.trim()))
+        })
+    }
+}
+
+/// Confirmations of a transaction mined at `block_height` when the chain tip
+/// is at `tip_height`.
+pub fn confirmations(block_height: u64, tip_height: u64) -> u32 {
+    (tip_height + 1).saturating_sub(block_height) as u32
+}
+
+/// Height of the mock chain's tip when it started.
+const MOCK_START_HEIGHT: i64 = 100;
+
+/// State of the mock Esplora: every address it is asked about holds
+/// `balance_sats`, in the mempool until `confirm_after_secs` have passed
+/// since the address was first queried. Every transaction exists and is mined
+/// `confirm_after_secs` after it was first queried; a block is found every
+/// `block_secs`.
+struct MockState {
+    balance_sats: u64,
+    confirm_after_secs: i64,
+    block_secs: i64,
+    started: DateTime<Utc>,
+    first_seen: Mutex<HashMap<String, DateTime<Utc>>>,
+}
+
+impl MockState {
+    fn first_seen(&self, key: &str) -> DateTime<Utc> {
+        *self
+            .first_seen
+            .lock()
+            .unwrap()
+            .entry(key.to_string())
+            .or_insert_with(Utc::now)
+    }
+
+    fn height_at(&self, time: DateTime<Utc>) -> i64 {
+        MOCK_START_HEIGHT + (time - self.started).num_seconds() / self.block_secs
+    }
+}
+
+async fn mock_address(path: web::Path<String>, state: web::Data<MockState>) -> impl Responder {
+    let address = path.into_inner();
+    let first_seen = state.first_seen(&address);
+    let confirmed = (Utc::now() - first_seen).num_seconds() >= state.confirm_after_secs;
+    let (chain, mempool) = if confirmed {
+        (state.balance_sats, 0)
+    } else {
+        (0, state.balance_sats)
+    };
+    let stats = |sum: u64| {
+        json!({
+            "funded_txo_count": u64::from(sum > 0),
+            "funded_txo_sum": sum,
+            "spent_txo_count": 0,
+            "spent_txo_sum": 0,
+            "tx_count": u64::from(sum > 0)
+        })
+    };
+
+    HttpResponse::Ok().json(json!({
+        "address": address,
+        "chain_stats": stats(chain),
+        "mempool_stats": stats(mempool)
+    }))
+}
+
+async fn mock_tx_status(path: web::Path<String>, state: web::Data<MockState>) -> impl Responder {
+    let mined_at = state.first_seen(&path) + Duration::seconds(state.confirm_after_secs);
+    if Utc::now() < mined_at {
+        return HttpResponse::Ok().json(json!({ "confirmed": false }));
+    }
+    HttpResponse::Ok().json(json!({
+        "confirmed": true,
+        "block_height": state.height_at(mined_at),
+        "block_time": mined_at.timestamp()
+    }))
+}
+
+async fn mock_tip_height(state: web::Data<MockState>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/plain")
+        .body(state.height_at(Utc::now()).to_string())
+}
+
+/// Serves a stand-in Esplora API for exercising the balance check and the
+/// confirmation wait offline.
+pub async fn serve_mock(
+    port: u16,
+    balance_sats: u64,
+    confirm_after_secs: u64,
+    block_secs: u64,
+) -> std::io::Result<()> {
+    log::info!(
+        "Mock Esplora on 127.0.0.1:{}: every address holds {} sats, confirmed after {}s, \
+         a block every {}s",
+        port,
+        balance_sats,
+        confirm_after_secs,
+        block_secs
+    );
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    mock_server(listener, balance_sats, confirm_after_secs, block_secs)?.await
+}
+
+/// The mock Esplora, serving on `listener` once awaited.
+pub(crate) fn mock_server(
+    listener: TcpListener,
+    balance_sats: u64,
+    confirm_after_secs: u64,
+    block_secs: u64,
+) -> std::io::Result<Server> {
+    let state = web::Data::new(MockState {
+        balance_sats,
+        confirm_after_secs: confirm_after_secs as i64,
+        block_secs: block_secs.max(1) as i64,
+        started: Utc::now(),
+        first_seen: Mutex::new(HashMap::new()),
+    });
+    Ok(HttpServer::new(move || {
+        App::new()
+            .app_data(state.clone())
+            .route("/address/{address}", web::get().to(mock_address))
+            .route("/tx/{txid}/status", web::get().to(mock_tx_status))
+            .route("/blocks/tip/height", web::get().to(mock_tip_height))
+    })
+    .listen(listener)?
+    .run())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ADDRESS: &str = "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx";
+
+    /// Starts a mock Esplora on a free port and returns a client for it.
+    fn start_mock(balance_sats: u64, confirm_after_secs: u64) -> EsploraClient {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = mock_server(listener, balance_sats, confirm_after_secs, 60).unwrap();
+        actix_web::rt::spawn(server);
+        EsploraClient::new(&url)
+    }
+
+    #[test]
+    fn confirmations_count_the_block_itself() {
+        assert_eq!(confirmations(100, 100), 1);
+        assert_eq!(confirmations(100, 102), 3);
+        assert_eq!(confirmations(101, 100), 0);
+    }
+
+    #[actix_web::test]
+    async fn unconfirmed_funds_show_as_mempool_balance() {
+        let esplora = start_mock(25000, 3600);
+
+        let balance = esplora.address_balance(ADDRESS).await.unwrap();
+        assert_eq!(balance.confirmed_sats, 0);
+        assert_eq!(balance.unconfirmed_sats, 25000);
+        assert_eq!(balance.total_sats(), 25000);
+
+        let status = esplora.tx_status("ab".repeat(32).as_str()).await.unwrap().unwrap();
+        assert!(!status.confirmed);
+        assert_eq!(status.block_height, None);
+    }
+
+    #[actix_web::test]
+    async fn confirmed_funds_count_from_the_tip() {
+        let esplora = start_mock(25000, 0);
+
+        let balance = esplora.address_balance(ADDRESS).await.unwrap();
+        assert_eq!(balance.confirmed_sats, 25000);
+        assert_eq!(balance.unconfirmed_sats, 0);
+
+        let status = esplora.tx_status("cd".repeat(32).as_str()).await.unwrap().unwrap();
+        assert!(status.confirmed);
+        let tip = esplora.tip_height().await.unwrap();
+        assert_eq!(tip, MOCK_START_HEIGHT as u64);
+        assert_eq!(confirmations(status.block_height.unwrap(), tip), 1);
+    }
+}
@@ -0,0 +1,122 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl From<LogLevel> for log::Level {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Debug => log::Level::Debug,
+            LogLevel::Info => log::Level::Info,
+            LogLevel::Warn => log::Level::Warn,
+            LogLevel::Error => log::Level::Error,
+        }
+    }
+}
+
+/// One entry in a run's event log.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LogEvent {
+    pub timestamp: DateTime<Utc>,
+    pub level: LogLevel,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub step: Option<u32>,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    #[schema(value_type = Object)]
+    pub fields: Map<String, Value>,
+}
+
+/// Filters for the event log endpoint; `level` is a minimum level.
+#[derive(Debug, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EventFilter {
+    pub level: Option<LogLevel>,
+    pub step: Option<u32>,
+}
+
+impl EventFilter {
+    pub fn matches(&self, event: &LogEvent) -> bool {
+        self.level.is_none_or(|level| event.level >= level)
+            && self.step.is_none_or(|step| event.step == Some(step))
+    }
+}
+
+/// Milliseconds from each step's first event to its last. A step that ran
+/// more than once, e.g. on a retried attempt, spans all of its attempts.
+pub fn step_durations(events: &[LogEvent]) -> BTreeMap<u32, i64> {
+    let mut spans: BTreeMap<u32, (DateTime<Utc>, DateTime<Utc>)> = BTreeMap::new();
+    for event in events {
+        let Some(step) = event.step else { continue };
+        spans
+            .entry(step)
+            .and_modify(|(first, last)| {
+                *first = (*first).min(event.timestamp);
+                *last = (*last).max(event.timestamp);
+            })
+            .or_insert((event.timestamp, event.timestamp));
+    }
+    spans
+        .into_iter()
+        .map(|(step, (first, last))| (step, (last - first).num_milliseconds()))
+        .collect()
+}
+
+/// Splits a `steps_completed` entry or step message like "Step 4: Created a
+/// new loan", with or without the "✓ " a completed step is logged with.
+pub fn parse_step_label(label: &str) -> Option<(u32, String)> {
+    let label = label.strip_prefix("✓ ").unwrap_or(label);
+    let (step, name) = label.strip_prefix("Step ")?.split_once(": ")?;
+    Some((step.parse().ok()?, name.to_string()))
+}
+
+pub fn to_json_lines(events: &[LogEvent]) -> String {
+    events
+        .iter()
+        .filter_map(|event| serde_json::to_string(event).ok())
+        .map(|line| line + "\n")
+        .collect()
+}
+
+/// Parses stored JSON lines. Lines that are not events (plain-text logs
+/// written before structured logging) become info events stamped with
+/// `fallback_timestamp`.
+pub fn from_json_lines(content: &str, fallback_timestamp: DateTime<Utc>) -> Vec<LogEvent> {
+    content
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            serde_json::from_str(line).unwrap_or_else(|_| LogEvent {
+                timestamp: fallback_timestamp,
+                level: LogLevel::Info,
+                step: None,
+                message: line.to_string(),
+                fields: Map::new(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_labels_parse_with_or_without_a_check_mark() {
+        let expected = Some((4, "Created a new loan".to_string()));
+        assert_eq!(parse_step_label("Step 4: Created a new loan"), expected);
+        assert_eq!(parse_step_label("✓ Step 4: Created a new loan"), expected);
+        assert_eq!(parse_step_label("Executing command: Step 4"), None);
+        assert_eq!(parse_step_label("Step four: Created a new loan"), None);
+    }
+}
@@ -0,0 +1,167 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use utoipa::ToSchema;
+
+/// Keys faucets are known to use for the funding transaction's id.
+const TXID_KEYS: &[&str] = &[
+    "txid",
+    "tx_id",
+    "txId",
+    "tx_hash",
+    "txHash",
+    "transaction_id",
+    "signature",
+];
+
+/// Keys faucets are known to use for the amount they sent.
+const AMOUNT_KEYS: &[&str] = &["amount", "sats", "amount_sats", "value"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FaucetAsset {
+    Btc,
+    LavaUsd,
+}
+
+impl FaucetAsset {
+    pub fn label(self) -> &'static str {
+        match self {
+            FaucetAsset::Btc => "BTC",
+            FaucetAsset::LavaUsd => "LavaUSD",
+        }
+    }
+}
+
+/// What a faucet reported for a successful funding request.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct FaucetReceipt {
+    pub asset: FaucetAsset,
+    pub http_status: u16,
+    pub attempts: u32,
+    /// Funding transaction (or Solana signature); unset when the response
+    /// didn't include one
+    pub txid: Option<String>,
+    /// Amount the faucet says it sent, in sats for BTC
+    pub amount: Option<u64>,
+    #[serde(default)]
+    pub requested_at: Option<DateTime<Utc>>,
+    /// Confirmation tracking, filled in by the confirmation wait
+    #[serde(default)]
+    pub confirmation: Option<Confirmation>,
+}
+
+/// When a funding transaction was mined and reached the required depth.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Confirmation {
+    /// Confirmations seen when the wait ended
+    pub confirmations: u32,
+    pub required: u32,
+    pub block_height: Option<u64>,
+    /// Timestamp of the block that mined the transaction
+    pub mined_at: Option<DateTime<Utc>>,
+    /// When `required` confirmations were first observed; unset if the wait
+    /// timed out
+    pub confirmed_at: Option<DateTime<Utc>>,
+    /// Seconds from the faucet request to `confirmed_at`
+    pub wait_secs: Option<i64>,
+}
+
+impl FaucetReceipt {
+    /// Reads the txid and amount out of a faucet's response body. Faucets
+    /// answer with a JSON object (possibly wrapped in `data` or `result`) or
+    /// with the bare transaction id.
+    pub fn parse(asset: FaucetAsset, http_status: u16, attempts: u32, body: &str) -> Self {
+        let (txid, amount) = match serde_json::from_str::<Value>(body) {
+            Ok(value) => (find_txid(&value), find_amount(&value)),
+            Err(_) => (bare_txid(body), None),
+        };
+        FaucetReceipt {
+            asset,
+            http_status,
+            attempts,
+            txid,
+            amount,
+            requested_at: None,
+            confirmation: None,
+        }
+    }
+}
+
+/// The object holding the faucet's fields, looking one level into common
+/// wrappers.
+fn fields(value: &Value) -> impl Iterator<Item = &serde_json::Map<String, Value>> {
+    let wrapped = ["data", "result"]
+        .into_iter()
+        .filter_map(move |key| value.get(key).and_then(Value::as_object));
+    value.as_object().into_iter().chain(wrapped)
+}
+
+fn find_txid(value: &Value) -> Option<String> {
+    if let Value::String(s) = value {
+        return bare_txid(s);
+    }
+    fields(value).find_map(|map| {
+        TXID_KEYS
+            .iter()
+            .find_map(|key| map.get(*key).and_then(Value::as_str))
+            .map(str::to_string)
+    })
+}
+
+fn find_amount(value: &Value) -> Option<u64> {
+    fields(value).find_map(|map| {
+        AMOUNT_KEYS.iter().find_map(|key| match map.get(*key)? {
+            Value::Number(n) => n.as_u64(),
+            Value::String(s) => s.parse().ok(),
+            _ => None,
+        })
+    })
+}
+
+/// Accepts a body that is nothing but a hex txid or a base58 signature.
+fn bare_txid(body: &str) -> Option<String> {
+    let body = body.trim().trim_matches('"');
+    let hex = body.len() == 64 && body.chars().all(|c| c.is_ascii_hexdigit());
+    let base58 = (43..=88).contains(&body.len()) && bs58::decode(body).into_vec().is_ok();
+    (hex || base58).then(|| body.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TXID: &str = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+
+    #[test]
+    fn reads_txid_and_amount_from_json() {
+        let body = format!(r#"{{"txid": "{}", "amount": 10000}}"#, TXID);
+        let receipt = FaucetReceipt::parse(FaucetAsset::Btc, 200, 1, &body);
+        assert_eq!(receipt.txid.as_deref(), Some(TXID));
+        assert_eq!(receipt.amount, Some(10000));
+    }
+
+    #[test]
+    fn looks_inside_wrappers_and_reads_string_amounts() {
+        let body = r#"{"data": {"signature": "sig", "sats": "2500"}}"#;
+        let receipt = FaucetReceipt::parse(FaucetAsset::LavaUsd, 200, 2, body);
+        assert_eq!(receipt.txid.as_deref(), Some("sig"));
+        assert_eq!(receipt.amount, Some(2500));
+        assert_eq!(receipt.attempts, 2);
+    }
+
+    #[test]
+    fn accepts_a_bare_txid() {
+        let receipt = FaucetReceipt::parse(FaucetAsset::Btc, 200, 1, &format!("\"{}\"\n", TXID));
+        assert_eq!(receipt.txid.as_deref(), Some(TXID));
+        assert_eq!(receipt.amount, None);
+    }
+
+    #[test]
+    fn ignores_bodies_without_a_txid() {
+        let receipt = FaucetReceipt::parse(FaucetAsset::Btc, 200, 1, "Funds sent!");
+        assert_eq!(receipt.txid, None);
+        let receipt = FaucetReceipt::parse(FaucetAsset::Btc, 200, 1, r#"{"status": "ok"}"#);
+        assert_eq!((receipt.txid, receipt.amount), (None, None));
+    }
+}
@@ -0,0 +1,248 @@
+use crate::config::LimitsConfig;
+use crate::metrics::Metrics;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// How long to tell clients to wait when every run slot is busy.
+const CONCURRENCY_RETRY_AFTER_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ThrottleReason {
+    Concurrency,
+    RateLimit,
+    DailyBudget,
+}
+
+#[derive(Debug)]
+pub struct Throttled {
+    pub reason: ThrottleReason,
+    pub retry_after_secs: u64,
+}
+
+impl fmt::Display for Throttled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.reason {
+            ThrottleReason::Concurrency => write!(f, "Too many test runs in progress"),
+            ThrottleReason::RateLimit => write!(f, "Run rate limit exceeded for this token"),
+            ThrottleReason::DailyBudget => write!(f, "Daily run budget exhausted"),
+        }
+    }
+}
+
+struct LimiterState {
+    active: u32,
+    per_caller: HashMap<String, VecDeque<DateTime<Utc>>>,
+    day: NaiveDate,
+    runs_today: u32,
+}
+
+/// Admission control for test runs, shared by every endpoint that starts one.
+pub struct RunLimiter {
+    config: LimitsConfig,
+    metrics: Arc<Metrics>,
+    state: Mutex<LimiterState>,
+}
+
+/// Holds a run slot; the slot is released when the permit is dropped.
+pub struct RunPermit {
+    limiter: Arc<RunLimiter>,
+    caller: String,
+    started_at: DateTime<Utc>,
+    /// Whether the permit took from the daily budget
+    budgeted: bool,
+}
+
+impl RunPermit {
+    /// Gives back the budget and rate window entry of a run that never
+    /// started, e.g. because no pooled wallet was free.
+    pub fn refund(self) {
+        let mut state = self.limiter.state.lock().unwrap();
+        if self.budgeted && state.day == self.started_at.date_naive() {
+            state.runs_today = state.runs_today.saturating_sub(1);
+        }
+        if let Some(starts) = state.per_caller.get_mut(&self.caller) {
+            if let Some(pos) = starts.iter().position(|start| *start == self.started_at) {
+                starts.remove(pos);
+            }
+        }
+        // Dropping `self` releases the concurrency slot
+    }
+}
+
+impl Drop for RunPermit {
+    fn drop(&mut self) {
+        let mut state = self.limiter.state.lock().unwrap();
+        state.active = state.active.saturating_sub(1);
+    }
+}
+
+impl RunLimiter {
+    /// `runs_today` seeds the daily budget so restarts don't reset it.
+    pub fn new(config: LimitsConfig, metrics: Arc<Metrics>, runs_today: u32) -> Self {
+        RunLimiter {
+            config,
+            metrics,
+            state: Mutex::new(LimiterState {
+                active: 0,
+                per_caller: HashMap::new(),
+                day: Utc::now().date_naive(),
+                runs_today,
+            }),
+        }
+    }
+
+    /// Reserves a run slot for `caller`, or explains why it has to wait.
+    pub fn try_acquire(self: &Arc<Self>, caller: &str) -> Result<RunPermit, Throttled> {
+        let now = Utc::now();
+        let mut state = self.state.lock().unwrap();
+
+        if state.day != now.date_naive() {
+            state.day = now.date_naive();
+            state.runs_today = 0;
+        }
+
+        if let Err(throttled) = self.check(&mut state, caller, now) {
+            self.metrics.record_throttled(throttled.reason);
+            return Err(throttled);
+        }
+
+        state.runs_today += 1;
+        Ok(self.admit(&mut state, caller, now, true))
+    }
+
+    /// Takes a run slot for a run admitted before a restart. It is never
+    /// refused, and doesn't take from the daily budget a second time, but
+    /// counts against the concurrency and per-token limits like a new run.
+    pub fn resume(self: &Arc<Self>, caller: &str) -> RunPermit {
+        let now = Utc::now();
+        let mut state = self.state.lock().unwrap();
+        self.admit(&mut state, caller, now, false)
+    }
+
+    fn admit(
+        self: &Arc<Self>,
+        state: &mut LimiterState,
+        caller: &str,
+        now: DateTime<Utc>,
+        budgeted: bool,
+    ) -> RunPermit {
+        state.active += 1;
+        state
+            .per_caller
+            .entry(caller.to_string())
+            .or_default()
+            .push_back(now);
+
+        RunPermit {
+            limiter: Arc::clone(self),
+            caller: caller.to_string(),
+            started_at: now,
+            budgeted,
+        }
+    }
+
+    fn check(&self, state: &mut LimiterState, caller: &str, now: DateTime<Utc>) -> Result<(), Throttled> {
+        let budget = self.config.daily_run_budget;
+        if budget > 0 && state.runs_today >= budget {
+            let midnight = (now.date_naive() + Duration::days(1))
+                .and_hms_opt(0, 0, 0)
+                .expect("midnight is valid")
+                .and_utc();
+            return Err(Throttled {
+                reason: ThrottleReason::DailyBudget,
+                retry_after_secs: (midnight - now).num_seconds().max(1) as u64,
+            });
+        }
+
+        let max = self.config.runs_per_token;
+        if max > 0 {
+            let window = Duration::seconds(self.config.rate_window_secs as i64);
+            let starts = state.per_caller.entry(caller.to_string()).or_default();
+            while starts.front().is_some_and(|start| *start + window <= now) {
+                starts.pop_front();
+            }
+            if starts.len() as u32 >= max {
+                let oldest = *starts.front().expect("window is full");
+                return Err(Throttled {
+                    reason: ThrottleReason::RateLimit,
+                    retry_after_secs: (oldest + window - now).num_seconds().max(1) as u64,
+                });
+            }
+        }
+
+        let max = self.config.max_concurrent_runs;
+        if max > 0 && state.active >= max {
+            return Err(Throttled {
+                reason: ThrottleReason::Concurrency,
+                retry_after_secs: CONCURRENCY_RETRY_AFTER_SECS,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limiter(max_concurrent_runs: u32, runs_per_token: u32, daily_run_budget: u32) -> Arc<RunLimiter> {
+        let config = LimitsConfig {
+            max_concurrent_runs,
+            runs_per_token,
+            rate_window_secs: 3600,
+            daily_run_budget,
+        };
+        Arc::new(RunLimiter::new(config, Arc::new(Metrics::default()), 0))
+    }
+
+    fn reason(result: Result<RunPermit, Throttled>) -> Option<ThrottleReason> {
+        result.err().map(|throttled| throttled.reason)
+    }
+
+    #[test]
+    fn dropping_a_permit_frees_its_slot() {
+        let limiter = limiter(1, 0, 0);
+        let permit = limiter.try_acquire("ci").unwrap();
+        assert_eq!(reason(limiter.try_acquire("ci")), Some(ThrottleReason::Concurrency));
+        drop(permit);
+        assert!(limiter.try_acquire("ci").is_ok());
+    }
+
+    #[test]
+    fn rate_limit_is_per_caller() {
+        let limiter = limiter(0, 1, 0);
+        let _first = limiter.try_acquire("ci").unwrap();
+        assert_eq!(reason(limiter.try_acquire("ci")), Some(ThrottleReason::RateLimit));
+        assert!(limiter.try_acquire("nightly").is_ok());
+    }
+
+    #[test]
+    fn daily_budget_counts_finished_runs() {
+        let limiter = limiter(0, 0, 2);
+        drop(limiter.try_acquire("ci").unwrap());
+        drop(limiter.try_acquire("ci").unwrap());
+        let throttled = limiter.try_acquire("ci").err().unwrap();
+        assert_eq!(throttled.reason, ThrottleReason::DailyBudget);
+        assert!(throttled.retry_after_secs >= 1);
+    }
+
+    #[test]
+    fn refunds_return_budget_and_rate_window() {
+        let limiter = limiter(1, 1, 1);
+        limiter.try_acquire("ci").unwrap().refund();
+        let _permit = limiter.try_acquire("ci").unwrap();
+        assert_eq!(reason(limiter.try_acquire("ci")), Some(ThrottleReason::DailyBudget));
+    }
+
+    #[test]
+    fn resumed_runs_take_a_slot_but_no_budget() {
+        let limiter = limiter(1, 0, 1);
+        let resumed = limiter.resume("ci");
+        assert_eq!(reason(limiter.try_acquire("ci")), Some(ThrottleReason::Concurrency));
+        drop(resumed);
+        assert!(limiter.try_acquire("ci").is_ok());
+    }
+}
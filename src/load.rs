@@ -0,0 +1,688 @@
+use crate::config::LoadConfig;
+use crate::db::TestResult;
+use crate::events;
+use crate::limits::ThrottleReason;
+use crate::matrix::CellStatus;
+use crate::network::BtcNetwork;
+use crate::scenario::NamedScenario;
+use crate::{AppState, TestOptions};
+use actix_web::web;
+use chrono::Utc;
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use tokio::time::{sleep, Duration};
+use utoipa::ToSchema;
+
+/// Longest a suite waits between attempts to get a run slot.
+const MAX_WAIT_SECS: u64 = 30;
+
+/// Upper bounds of the latency histogram buckets in milliseconds; a last,
+/// open-ended bucket takes anything slower.
+const BUCKETS_MS: [i64; 12] = [
+    100, 250, 500, 1_000, 2_500, 5_000, 10_000, 30_000, 60_000, 120_000, 300_000, 600_000,
+];
+
+/// Suites to run side by side, each with its own wallet and workspace.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct LoadRequest {
+    /// How many suites to run in parallel; at most `load.max_suites`
+    pub suites: u32,
+    /// Seconds over which the suites start, evenly spaced; 0 starts them all
+    /// at once
+    #[serde(default)]
+    pub ramp_up_secs: u64,
+    /// Negative scenario or scenario file every suite runs; unset runs the
+    /// standard borrow/repay flow
+    pub scenario: Option<String>,
+    /// Defaults to `suite.network`
+    pub network: Option<BtcNetwork>,
+    pub skip_faucet: Option<bool>,
+    /// Finished load run to compare with; defaults to the stored baseline
+    /// if it ran the same scenario
+    pub baseline_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LoadSuite {
+    pub index: u32,
+    pub status: CellStatus,
+    /// When the suite got its run slot, after the ramp-up delay
+    pub started_at: Option<String>,
+    /// The suite's stored result, once it has finished
+    pub result_id: Option<String>,
+    pub details: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct HistogramBucket {
+    /// Upper bound in milliseconds; unset for the last, open-ended bucket
+    pub le_ms: Option<i64>,
+    pub count: u32,
+}
+
+/// Latency and errors of one step across a load run's suites.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StepLatency {
+    pub step: u32,
+    pub name: String,
+    /// Suites that ran the step
+    pub attempts: u32,
+    /// Suites that failed at the step
+    pub errors: u32,
+    pub error_rate: f64,
+    /// Percentiles over the suites that completed the step; unset when none did
+    pub p50_ms: Option<i64>,
+    pub p90_ms: Option<i64>,
+    pub p99_ms: Option<i64>,
+    pub max_ms: Option<i64>,
+    pub histogram: Vec<HistogramBucket>,
+}
+
+/// One step's percentiles next to the baseline's. Changes are in percent.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct StepComparison {
+    pub step: u32,
+    pub name: String,
+    pub p50_ms: Option<i64>,
+    pub baseline_p50_ms: Option<i64>,
+    pub p50_change_pct: Option<f64>,
+    pub p90_ms: Option<i64>,
+    pub baseline_p90_ms: Option<i64>,
+    pub p90_change_pct: Option<f64>,
+    pub p99_ms: Option<i64>,
+    pub baseline_p99_ms: Option<i64>,
+    pub p99_change_pct: Option<f64>,
+    pub error_rate: f64,
+    pub baseline_error_rate: f64,
+    /// A percentile grew by more than `load.regression_threshold_pct` and
+    /// `load.regression_min_ms`, or the step failed more often than in the
+    /// baseline
+    pub regressed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct BaselineComparison {
+    pub baseline_id: String,
+    pub error_rate: f64,
+    pub baseline_error_rate: f64,
+    /// Steps of either run, in order
+    pub steps: Vec<StepComparison>,
+    pub regressed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LoadReport {
+    pub total: u32,
+    pub passed: u32,
+    pub failed: u32,
+    pub inconclusive: u32,
+    pub skipped: u32,
+    /// Failed and inconclusive suites over the suites that ran
+    pub error_rate: f64,
+    /// Seconds from the first suite starting to the last one finishing
+    pub wall_secs: f64,
+    pub steps: Vec<StepLatency>,
+    /// Unset when there was no baseline to compare with
+    pub comparison: Option<BaselineComparison>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct LoadRun {
+    pub id: String,
+    pub requested_by: String,
+    pub network: BtcNetwork,
+    pub ramp_up_secs: u64,
+    pub scenario: Option<String>,
+    pub skip_faucet: bool,
+    pub created_at: String,
+    /// Unset while suites are still pending or running
+    pub finished_at: Option<String>,
+    /// Whether later load runs compare with this one by default
+    pub baseline: bool,
+    pub suites: Vec<LoadSuite>,
+    /// Set once every suite has finished
+    pub report: Option<LoadReport>,
+}
+
+impl LoadRun {
+    /// Renders the report as Markdown: outcome counts, then one row per step.
+    pub fn to_markdown(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "# Load run {}\n", self.id);
+        let Some(report) = &self.report else {
+            let _ = writeln!(out, "Still running: {} suites on {}", self.suites.len(), self.network);
+            return out;
+        };
+        let _ = writeln!(
+            out,
+            "{} suites on {}, ramped up over {}s, finished in {:.0}s: {} passed, {} failed, {} inconclusive, {} skipped (error rate {:.1}%)\n",
+            report.total,
+            self.network,
+            self.ramp_up_secs,
+            report.wall_secs,
+            report.passed,
+            report.failed,
+            report.inconclusive,
+            report.skipped,
+            report.error_rate * 100.0
+        );
+        let _ = writeln!(out, "| Step | Name | Runs | Errors | p50 | p90 | p99 | Max |");
+        let _ = writeln!(out, "| --- | --- | --- | --- | --- | --- | --- | --- |");
+        for step in &report.steps {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} | {} | {} | {} |",
+                step.step,
+                step.name,
+                step.attempts,
+                step.errors,
+                format_ms(step.p50_ms),
+                format_ms(step.p90_ms),
+                format_ms(step.p99_ms),
+                format_ms(step.max_ms)
+            );
+        }
+
+        if let Some(comparison) = &report.comparison {
+            let _ = writeln!(
+                out,
+                "\n## Compared with {}{}\n",
+                comparison.baseline_id,
+                if comparison.regressed { " (regressed)" } else { "" }
+            );
+            let _ = writeln!(
+                out,
+                "Error rate {:.1}% (baseline {:.1}%)\n",
+                comparison.error_rate * 100.0,
+                comparison.baseline_error_rate * 100.0
+            );
+            let _ = writeln!(out, "| Step | Name | p50 | p90 | p99 | Errors | Regressed |");
+            let _ = writeln!(out, "| --- | --- | --- | --- | --- | --- | --- |");
+            for step in &comparison.steps {
+                let _ = writeln!(
+                    out,
+                    "| {} | {} | {} | {} | {} | {:.1}% ({:.1}%) | {} |",
+                    step.step,
+                    step.name,
+                    format_change(step.p50_ms, step.baseline_p50_ms, step.p50_change_pct),
+                    format_change(step.p90_ms, step.baseline_p90_ms, step.p90_change_pct),
+                    format_change(step.p99_ms, step.baseline_p99_ms, step.p99_change_pct),
+                    step.error_rate * 100.0,
+                    step.baseline_error_rate * 100.0,
+                    if step.regressed { "yes" } else { "" }
+                );
+            }
+        }
+        out
+    }
+}
+
+fn format_ms(ms: Option<i64>) -> String {
+    ms.map_or("-".to_string(), |ms| format!("{} ms", ms))
+}
+
+fn format_change(ms: Option<i64>, baseline_ms: Option<i64>, change_pct: Option<f64>) -> String {
+    match change_pct {
+        Some(change) => format!("{} ({:+.0}%)", format_ms(ms), change),
+        None => format!("{} (baseline {})", format_ms(ms), format_ms(baseline_ms)),
+    }
+}
+
+/// A new load run with every suite pending.
+pub fn new_run(requested_by: String, network: BtcNetwork, request: &LoadRequest) -> LoadRun {
+    LoadRun {
+        id: uuid::Uuid::new_v4().to_string(),
+        requested_by,
+        network,
+        ramp_up_secs: request.ramp_up_secs,
+        scenario: request.scenario.clone(),
+        skip_faucet: request.skip_faucet.unwrap_or(false),
+        created_at: Utc::now().to_rfc3339(),
+        finished_at: None,
+        baseline: false,
+        suites: (0..request.suites)
+            .map(|index| LoadSuite {
+                index,
+                status: CellStatus::Pending,
+                started_at: None,
+                result_id: None,
+                details: None,
+            })
+            .collect(),
+        report: None,
+    }
+}
+
+/// Starts every suite of `run` on its ramp-up schedule, then stores the
+/// report, compared with `baseline` when there is one.
+pub async fn execute(
+    data: web::Data<AppState>,
+    run: LoadRun,
+    scenario: Option<NamedScenario>,
+    baseline: Option<LoadRun>,
+) {
+    let started_at = Utc::now();
+    let count = run.suites.len().max(1) as u64;
+    let results: Vec<TestResult> = join_all(run.suites.iter().map(|suite| {
+        let delay = Duration::from_millis(run.ramp_up_secs * 1000 * suite.index as u64 / count);
+        run_suite(&data, &run, suite, scenario.clone(), delay)
+    }))
+    .await
+    .into_iter()
+    .flatten()
+    .collect();
+
+    let wall_secs = (Utc::now() - started_at).num_milliseconds() as f64 / 1000.0;
+    let mut report = build_report(&results, run.suites.len() as u32, wall_secs);
+    if let Some(baseline_report) = baseline.as_ref().and_then(|baseline| baseline.report.as_ref()) {
+        let id = baseline.as_ref().map(|baseline| baseline.id.clone()).unwrap_or_default();
+        report.comparison = Some(compare(
+            &report,
+            &id,
+            baseline_report,
+            &data.config.load,
+        ));
+    }
+
+    if let Err(e) = data.db.lock().unwrap().finish_load_run(&run.id, &report) {
+        log::error!("Failed to finish load run {}: {}", run.id, e);
+    }
+    log::info!(
+        "Load run {} finished: {} of {} suites passed",
+        run.id,
+        report.passed,
+        report.total
+    );
+}
+
+async fn run_suite(
+    data: &AppState,
+    run: &LoadRun,
+    suite: &LoadSuite,
+    scenario: Option<NamedScenario>,
+    delay: Duration,
+) -> Option<TestResult> {
+    let update = |status: CellStatus, result_id: Option<&str>, details: Option<&str>| {
+        let db = data.db.lock().unwrap();
+        if let Err(e) = db.update_load_suite(&run.id, suite.index, status, result_id, details) {
+            log::error!("Failed to update suite {} of load run {}: {}", suite.index, run.id, e);
+        }
+    };
+
+    sleep(delay).await;
+    let permit = loop {
+        match data.limiter.try_acquire(&run.requested_by) {
+            Ok(permit) => break permit,
+            Err(throttled) if throttled.reason == ThrottleReason::DailyBudget => {
+                update(CellStatus::Skipped, None, Some(&throttled.to_string()));
+                return None;
+            }
+            Err(throttled) => sleep(Duration::from_secs(throttled.retry_after_secs.min(MAX_WAIT_SECS))).await,
+        }
+    };
+
+    // The CLI gets absolute output paths, so the workspace must be absolute too
+    let workspace = Path::new(&data.config.suite.output_dir)
+        .join("load")
+        .join(&run.id)
+        .join(format!("suite-{}", suite.index));
+    let workspace = match fs::create_dir_all(&workspace).and_then(|_| fs::canonicalize(&workspace)) {
+        Ok(workspace) => workspace,
+        Err(e) => {
+            permit.refund();
+            update(CellStatus::Skipped, None, Some(&format!("Failed to create workspace: {}", e)));
+            return None;
+        }
+    };
+
+    if let Err(e) = data.db.lock().unwrap().start_load_suite(&run.id, suite.index) {
+        log::error!("Failed to update suite {} of load run {}: {}", suite.index, run.id, e);
+    }
+    // No mnemonic and no pooled wallet: every suite generates its own
+    let options = TestOptions {
+        skip_faucet: Some(run.skip_faucet),
+        network: Some(run.network),
+        scenario: run.scenario.clone(),
+        ..TestOptions::default()
+    };
+    let test_suite = crate::build_suite(data, &options, run.network, None, None, scenario)
+        .with_workspace(workspace);
+    let result = crate::finish_run(
+        data,
+        &options,
+        None,
+        test_suite,
+        format!("{} (load {})", run.requested_by, run.id),
+    )
+    .await;
+
+    let status = if result.success {
+        CellStatus::Passed
+    } else if result.inconclusive {
+        CellStatus::Inconclusive
+    } else {
+        CellStatus::Failed
+    };
+    update(status, Some(&result.id), Some(&result.details));
+    Some(result)
+}
+
+/// Outcome counts and per-step latencies of the suites that ran; suites
+/// without a result count as skipped.
+pub fn build_report(results: &[TestResult], total: u32, wall_secs: f64) -> LoadReport {
+    let passed = results.iter().filter(|result| result.success).count() as u32;
+    let inconclusive = results
+        .iter()
+        .filter(|result| !result.success && result.inconclusive)
+        .count() as u32;
+    let failed = results.len() as u32 - passed - inconclusive;
+
+    let mut names: BTreeMap<u32, String> = BTreeMap::new();
+    let mut durations: BTreeMap<u32, Vec<i64>> = BTreeMap::new();
+    let mut errors: BTreeMap<u32, u32> = BTreeMap::new();
+    for result in results {
+        // Prefer what a step completed; fall back to how it started
+        for label in &result.steps_completed {
+            if let Some((step, name)) = events::parse_step_label(label) {
+                names.entry(step).or_insert(name);
+            }
+        }
+        for event in &result.logs {
+            if let Some((step, name)) = events::parse_step_label(&event.message) {
+                names.entry(step).or_insert(name);
+            }
+        }
+        let failed_step = result
+            .error
+            .as_ref()
+            .filter(|_| !result.success)
+            .and_then(|error| error.step);
+        if let Some(step) = failed_step {
+            *errors.entry(step).or_default() += 1;
+        }
+        for (step, ms) in events::step_durations(&result.logs) {
+            if Some(step) != failed_step {
+                durations.entry(step).or_default().push(ms);
+            }
+        }
+    }
+
+    let mut steps: Vec<u32> = durations.keys().chain(errors.keys()).copied().collect();
+    steps.sort_unstable();
+    steps.dedup();
+    let steps = steps
+        .into_iter()
+        .map(|step| {
+            let mut samples = durations.remove(&step).unwrap_or_default();
+            samples.sort_unstable();
+            let errors = errors.get(&step).copied().unwrap_or(0);
+            let attempts = samples.len() as u32 + errors;
+            StepLatency {
+                step,
+                name: names.get(&step).cloned().unwrap_or_default(),
+                attempts,
+                errors,
+                error_rate: ratio(errors, attempts),
+                p50_ms: percentile(&samples, 50.0),
+                p90_ms: percentile(&samples, 90.0),
+                p99_ms: percentile(&samples, 99.0),
+                max_ms: samples.last().copied(),
+                histogram: histogram(&samples),
+            }
+        })
+        .collect();
+
+    LoadReport {
+        total,
+        passed,
+        failed,
+        inconclusive,
+        skipped: total.saturating_sub(results.len() as u32),
+        error_rate: ratio(failed + inconclusive, results.len() as u32),
+        wall_secs,
+        steps,
+        comparison: None,
+    }
+}
+
+fn ratio(part: u32, whole: u32) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+/// Nearest-rank percentile of sorted samples.
+fn percentile(sorted: &[i64], pct: f64) -> Option<i64> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = (pct / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+fn histogram(samples: &[i64]) -> Vec<HistogramBucket> {
+    let mut buckets: Vec<HistogramBucket> = BUCKETS_MS
+        .iter()
+        .map(|&le_ms| HistogramBucket {
+            le_ms: Some(le_ms),
+            count: 0,
+        })
+        .chain(std::iter::once(HistogramBucket {
+            le_ms: None,
+            count: 0,
+        }))
+        .collect();
+    for &ms in samples {
+        let index = BUCKETS_MS
+            .iter()
+            .position(|&le_ms| ms <= le_ms)
+            .unwrap_or(BUCKETS_MS.len());
+        buckets[index].count += 1;
+    }
+    buckets
+}
+
+/// Compares each step's percentiles and error rate with the baseline's.
+pub fn compare(
+    report: &LoadReport,
+    baseline_id: &str,
+    baseline: &LoadReport,
+    config: &LoadConfig,
+) -> BaselineComparison {
+    let mut steps: Vec<u32> = report
+        .steps
+        .iter()
+        .chain(&baseline.steps)
+        .map(|step| step.step)
+        .collect();
+    steps.sort_unstable();
+    steps.dedup();
+
+    let steps: Vec<StepComparison> = steps
+        .into_iter()
+        .map(|number| {
+            let current = report.steps.iter().find(|step| step.step == number);
+            let base = baseline.steps.iter().find(|step| step.step == number);
+            let p50 = current.and_then(|step| step.p50_ms);
+            let p90 = current.and_then(|step| step.p90_ms);
+            let p99 = current.and_then(|step| step.p99_ms);
+            let base_p50 = base.and_then(|step| step.p50_ms);
+            let base_p90 = base.and_then(|step| step.p90_ms);
+            let base_p99 = base.and_then(|step| step.p99_ms);
+            let changes = [change_pct(p50, base_p50), change_pct(p90, base_p90), change_pct(p99, base_p99)];
+            // Percentages alone would flag a 5 ms step that took 10 ms
+            let slower = [(p50, base_p50), (p90, base_p90), (p99, base_p99)]
+                .iter()
+                .zip(changes)
+                .any(|((current, base), change)| {
+                    change.is_some_and(|change| change > config.regression_threshold_pct)
+                        && current.unwrap_or(0) - base.unwrap_or(0) > config.regression_min_ms
+                });
+            let error_rate = current.map_or(0.0, |step| step.error_rate);
+            let baseline_error_rate = base.map_or(0.0, |step| step.error_rate);
+            StepComparison {
+                step: number,
+                name: current
+                    .or(base)
+                    .map(|step| step.name.clone())
+                    .unwrap_or_default(),
+                p50_ms: p50,
+                baseline_p50_ms: base_p50,
+                p50_change_pct: changes[0],
+                p90_ms: p90,
+                baseline_p90_ms: base_p90,
+                p90_change_pct: changes[1],
+                p99_ms: p99,
+                baseline_p99_ms: base_p99,
+                p99_change_pct: changes[2],
+                error_rate,
+                baseline_error_rate,
+                regressed: slower || error_rate > baseline_error_rate,
+            }
+        })
+        .collect();
+
+    BaselineComparison {
+        baseline_id: baseline_id.to_string(),
+        error_rate: report.error_rate,
+        baseline_error_rate: baseline.error_rate,
+        regressed: steps.iter().any(|step| step.regressed) || report.error_rate > baseline.error_rate,
+        steps,
+    }
+}
+
+/// Change from `baseline` to `current` in percent; unset unless both are
+/// known and the baseline isn't zero.
+fn change_pct(current: Option<i64>, baseline: Option<i64>) -> Option<f64> {
+    match (current, baseline) {
+        (Some(current), Some(baseline)) if baseline > 0 => {
+            Some((current - baseline) as f64 / baseline as f64 * 100.0)
+        }
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{LogEvent, LogLevel};
+    use crate::test_suite::TestError;
+    use serde_json::Map;
+
+    /// A result whose steps each ran for the given milliseconds, one after
+    /// the other.
+    fn result(id: &str, step_ms: &[(u32, i64)]) -> TestResult {
+        let mut result = TestResult::empty(id);
+        let mut at = result.timestamp;
+        for &(step, ms) in step_ms {
+            for offset in [0, ms] {
+                result.logs.push(LogEvent {
+                    timestamp: at + chrono::Duration::milliseconds(offset),
+                    level: LogLevel::Info,
+                    step: Some(step),
+                    message: format!("Step {}: Step {}", step, step),
+                    fields: Map::new(),
+                });
+            }
+            at += chrono::Duration::milliseconds(ms);
+        }
+        result
+    }
+
+    /// A report with one step whose percentiles are all `ms`.
+    fn report(ms: i64, error_rate: f64) -> LoadReport {
+        LoadReport {
+            total: 1,
+            passed: 1,
+            failed: 0,
+            inconclusive: 0,
+            skipped: 0,
+            error_rate,
+            wall_secs: 1.0,
+            steps: vec![StepLatency {
+                step: 1,
+                name: "Step 1".to_string(),
+                attempts: 1,
+                errors: 0,
+                error_rate,
+                p50_ms: Some(ms),
+                p90_ms: Some(ms),
+                p99_ms: Some(ms),
+                max_ms: Some(ms),
+                histogram: histogram(&[ms]),
+            }],
+            comparison: None,
+        }
+    }
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let samples: Vec<i64> = (1..=10).collect();
+        assert_eq!(percentile(&samples, 50.0), Some(5));
+        assert_eq!(percentile(&samples, 90.0), Some(9));
+        assert_eq!(percentile(&samples, 99.0), Some(10));
+        assert_eq!(percentile(&[7], 50.0), Some(7));
+        assert_eq!(percentile(&[], 50.0), None);
+    }
+
+    #[test]
+    fn histogram_buckets_are_inclusive_with_an_open_last_bucket() {
+        let buckets = histogram(&[100, 101, 600_001]);
+        assert_eq!(buckets.len(), BUCKETS_MS.len() + 1);
+        assert_eq!(buckets[0].count, 1);
+        assert_eq!(buckets[1].count, 1);
+        assert_eq!(buckets.last().unwrap().le_ms, None);
+        assert_eq!(buckets.last().unwrap().count, 1);
+    }
+
+    #[test]
+    fn report_leaves_failed_steps_out_of_latencies() {
+        let passed = result("a", &[(1, 1000), (2, 2000)]);
+        let mut failed = result("b", &[(1, 2000), (2, 500)]);
+        failed.success = false;
+        failed.error = Some(TestError::process("borrow init failed").at_step(2));
+
+        let report = build_report(&[passed, failed], 3, 10.0);
+        assert_eq!((report.passed, report.failed, report.skipped), (1, 1, 1));
+        assert_eq!(report.error_rate, 0.5);
+
+        let step1 = &report.steps[0];
+        assert_eq!((step1.attempts, step1.errors), (2, 0));
+        assert_eq!((step1.p50_ms, step1.p90_ms), (Some(1000), Some(2000)));
+        let step2 = &report.steps[1];
+        assert_eq!((step2.attempts, step2.errors), (2, 1));
+        assert_eq!(step2.error_rate, 0.5);
+        assert_eq!(step2.max_ms, Some(2000));
+    }
+
+    #[test]
+    fn much_slower_steps_regress() {
+        let comparison = compare(&report(2000, 0.0), "base", &report(1000, 0.0), &LoadConfig::default());
+        assert_eq!(comparison.steps[0].p50_change_pct, Some(100.0));
+        assert!(comparison.steps[0].regressed);
+        assert!(comparison.regressed);
+    }
+
+    #[test]
+    fn slowdowns_below_either_threshold_dont_regress() {
+        let config = LoadConfig::default();
+        // Tripled, but only 200 ms slower
+        assert!(!compare(&report(300, 0.0), "base", &report(100, 0.0), &config).regressed);
+        // A second slower, but only 10% slower
+        assert!(!compare(&report(11000, 0.0), "base", &report(10000, 0.0), &config).regressed);
+    }
+
+    #[test]
+    fn higher_error_rates_regress() {
+        let comparison = compare(&report(1000, 0.5), "base", &report(1000, 0.0), &LoadConfig::default());
+        assert!(comparison.steps[0].regressed);
+        assert!(comparison.regressed);
+    }
+}
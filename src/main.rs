@@ -1,16 +1,51 @@
+mod address;
+mod artifacts;
+mod auth;
+mod cli;
+mod cli_binary;
+mod config;
 mod db;
+mod diff;
+mod esplora;
+mod events;
+mod faucet;
+mod limits;
+mod load;
+mod matrix;
+mod metrics;
+mod negative;
+mod network;
+mod openapi;
+mod scenario;
+mod secrets;
 mod test_suite;
+mod wallet_pool;
 
-use actix_web::{web, App, HttpResponse, HttpServer, Responder};
+use actix_web::{middleware, web, App, HttpResponse, HttpServer, Responder};
+use address::AddressScheme;
+use artifacts::{ArtifactStore, LogChunk, LogRange};
+use auth::Caller;
 use chrono::Utc;
-use db::{Database, TestResult};
+use clap::Parser;
+use cli::{Cli, Command};
+use config::{Config, LoanConfig};
+use db::{AuditEntry, CliVersionSummary, Database, PooledWallet, ResultFilter, TestResult};
+use diff::RunDiff;
+use events::{EventFilter, LogEvent};
+use limits::RunLimiter;
+use load::{LoadRequest, LoadRun};
+use matrix::{MatrixRequest, MatrixRun};
+use metrics::Metrics;
+use negative::NegativeScenario;
+use network::BtcNetwork;
+use scenario::{CheckpointSink, NamedScenario, ScenarioCheckpoint, ScenarioFile};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use serde_json::json;
-use std::env;
-use std::sync::Mutex;
+use std::sync::{Arc, Mutex};
 use test_suite::TestSuite;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 struct ApiResponse<T> {
     success: bool,
     message: String,
@@ -19,18 +54,64 @@ struct ApiResponse<T> {
     timestamp: String,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize, ToSchema)]
 struct TestOptions {
     #[serde(skip_serializing_if = "Option::is_none")]
     mnemonic: Option<String>,
+    /// Skip the faucet because the wallet is already funded
     #[serde(skip_serializing_if = "Option::is_none")]
     skip_faucet: Option<bool>,
+    /// Length of a generated mnemonic: 12, 18 or 24 words
+    #[serde(skip_serializing_if = "Option::is_none")]
+    mnemonic_words: Option<usize>,
+    /// BIP39 passphrase; requires `server.secret_key` to store it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    passphrase: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    account_index: Option<u32>,
+    /// Defaults to `suite.network`; mainnet requires `suite.allow_mainnet`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    network: Option<BtcNetwork>,
+    /// Defaults to `suite.address_scheme`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    address_scheme: Option<AddressScheme>,
+    /// Overrides the scheme's standard path, e.g. `m/84'/1'/0'/0/5`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    derivation_path: Option<String>,
+    /// Run a scenario file (see `GET /scenario-files`) or a negative
+    /// scenario (see `GET /scenarios`) instead of the borrow/repay flow
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scenario: Option<String>,
+}
+
+impl TestOptions {
+    /// Pooled wallets are derived with the default passphrase and path, so
+    /// only runs that don't customise the wallet can use them. Scenarios may
+    /// need an unfunded wallet and always generate one.
+    fn uses_default_wallet(&self) -> bool {
+        self.mnemonic.is_none()
+            && self.passphrase.is_none()
+            && self.account_index.is_none()
+            && self.address_scheme.is_none()
+            && self.derivation_path.is_none()
+            && self.scenario.is_none()
+    }
 }
 
 struct AppState {
     db: Mutex<Database>,
+    config: Config,
+    metrics: Arc<Metrics>,
+    limiter: Arc<RunLimiter>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "server",
+    security(()),
+    responses((status = 200, description = "Server is up", body = ApiResponse<Object>))
+)]
 async fn health_check() -> impl Responder {
     let response = ApiResponse {
         success: true,
@@ -41,51 +122,826 @@ async fn health_check() -> impl Responder {
     HttpResponse::Ok().json(response)
 }
 
-
+#[utoipa::path(
+    post,
+    path = "/run-test",
+    tag = "runs",
+    request_body = TestOptions,
+    responses(
+        (status = 200, description = "Run finished; `success` reports the outcome", body = ApiResponse<TestResult>),
+        (status = 202, description = "A resumable scenario started in the background; its result is saved under the returned `id`", body = ApiResponse<Object>),
+        (status = 400, description = "The run options are not allowed by the config", body = ApiResponse<TestResult>),
+        (status = 429, description = "Throttled by run limits; see `Retry-After`", body = ApiResponse<TestResult>),
+        (status = 503, description = "Every pooled wallet is in use; see `Retry-After`", body = ApiResponse<TestResult>)
+    )
+)]
 async fn run_test(
     options: web::Json<TestOptions>,
+    caller: Caller,
     data: web::Data<AppState>
 ) -> impl Responder {
-    let mut test_suite = TestSuite::new();
+    let network = options.network.unwrap_or(data.config.suite.network);
+    let allowed = data.config.suite.check_network(network).and_then(|_| {
+        match (&options.passphrase, &data.config.server.secret_key) {
+            (Some(_), None) => Err("Set server.secret_key to run with a BIP39 passphrase".to_string()),
+            _ => Ok(()),
+        }
+    }).and_then(|_| {
+        options
+            .scenario
+            .as_deref()
+            .map(|name| NamedScenario::find(&data.config.suite, name))
+            .transpose()
+    }).and_then(|scenario| match &scenario {
+        Some(NamedScenario::File(file)) if file.definition.resumable && options.passphrase.is_some() => {
+            Err("Resumable scenarios can't be run with a BIP39 passphrase".to_string())
+        }
+        _ => Ok(scenario),
+    });
+    let scenario = match allowed {
+        Ok(scenario) => scenario,
+        Err(message) => {
+            let response = ApiResponse {
+                success: false,
+                message,
+                data: None::<TestResult>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            return HttpResponse::BadRequest().json(response);
+        }
+    };
+
+    // Held until the run finishes to count against the concurrency limit
+    let permit = match data.limiter.try_acquire(&caller.name) {
+        Ok(permit) => permit,
+        Err(throttled) => {
+            let response = ApiResponse {
+                success: false,
+                message: throttled.to_string(),
+                data: None::<TestResult>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            return HttpResponse::TooManyRequests()
+                .insert_header(("Retry-After", throttled.retry_after_secs.to_string()))
+                .json(response);
+        }
+    };
+    // Lease a funded wallet instead of generating one when the pool is on
+    let wallet = match lease_wallet(&data, &options, network) {
+        Ok(wallet) => wallet,
+        Err(exhausted) => {
+            // The run never starts, so it mustn't use up the caller's budget
+            permit.refund();
+            let response = ApiResponse {
+                success: false,
+                message: exhausted.to_string(),
+                data: None::<TestResult>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            return HttpResponse::ServiceUnavailable()
+                .insert_header(("Retry-After", "60"))
+                .json(response);
+        }
+    };
+
+    // Resumable scenarios can run for days, so don't keep the client waiting
+    if let Some(NamedScenario::File(file)) = scenario.as_ref().filter(|_| wallet.is_none()) {
+        if file.definition.resumable {
+            let name = file.name.clone();
+            let test_suite = build_suite(&data, &options, network, None, None, scenario)
+                .with_checkpoints(checkpoint_sink(&data.config, caller.name.clone()));
+            let run_id = test_suite.id().to_string();
+            log::info!("Starting resumable scenario '{}' as run {}", name, run_id);
+            let data = data.clone();
+            let options = options.into_inner();
+            // The permit moves into the task so the run keeps its slot to the end
+            actix_web::rt::spawn(async move {
+                let _permit = permit;
+                run_resumable(&data, &options, test_suite, caller.name).await;
+            });
+
+            let response = ApiResponse {
+                success: true,
+                message: format!(
+                    "Scenario '{}' started in the background; its result will be saved as {}",
+                    name, run_id
+                ),
+                data: Some(json!({ "id": run_id, "scenario": name })),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            return HttpResponse::Accepted().json(response);
+        }
+    }
+
+    let result = execute_run(&data, &options, network, wallet, None, scenario, caller.name).await;
+    drop(permit);
+    let success = result.success;
+
+    // Create and return the response
+    let response = ApiResponse {
+        success,
+        message: if success {
+            "Test completed successfully".to_string()
+        } else {
+            format!("Test failed: {}", result.details)
+        },
+        data: Some(result),
+        timestamp: Utc::now().to_rfc3339(),
+    };
     
+    // Always return 200 OK; failures are reported via success: false
+    HttpResponse::Ok().json(response)
+}
+
+/// Leases a pooled wallet for runs that use the default wallet while the
+/// pool is on. A database error is logged and the run generates a wallet.
+fn lease_wallet(
+    data: &AppState,
+    options: &TestOptions,
+    network: BtcNetwork,
+) -> Result<Option<PooledWallet>, wallet_pool::PoolExhausted> {
+    if !(data.config.pool.enabled && options.uses_default_wallet()) {
+        return Ok(None);
+    }
+    match wallet_pool::lease(&data.db.lock().unwrap(), &data.config.pool, network) {
+        Ok(leased) => leased,
+        Err(e) => {
+            log::error!("Failed to lease a pooled wallet: {}", e);
+            Ok(None)
+        }
+    }
+}
+
+/// Runs the suite with `options`, settles the pool and saves the result.
+/// `scenario` is what `options.scenario` names, resolved by the caller.
+async fn execute_run(
+    data: &AppState,
+    options: &TestOptions,
+    network: BtcNetwork,
+    wallet: Option<PooledWallet>,
+    loan: Option<LoanConfig>,
+    scenario: Option<NamedScenario>,
+    triggered_by: String,
+) -> TestResult {
+    let test_suite = build_suite(data, options, network, wallet.as_ref(), loan, scenario);
+    finish_run(data, options, wallet, test_suite, triggered_by).await
+}
+
+/// A suite set up the way `options` ask for.
+fn build_suite(
+    data: &AppState,
+    options: &TestOptions,
+    network: BtcNetwork,
+    wallet: Option<&PooledWallet>,
+    loan: Option<LoanConfig>,
+    scenario: Option<NamedScenario>,
+) -> TestSuite {
+    let mut test_suite = TestSuite::new(data.config.suite.clone()).with_network(network);
+    if let Some(wallet) = wallet {
+        test_suite = wallet_pool::apply(test_suite, &data.config.pool, wallet);
+    }
+    if let Some(loan) = loan {
+        test_suite = test_suite.with_loan(loan);
+    }
+
     // Apply options if provided
     if let Some(mnemonic) = &options.mnemonic {
         test_suite = test_suite.with_mnemonic(mnemonic.clone());
     }
- 
-    
+    if let Some(words) = options.mnemonic_words {
+        test_suite = test_suite.with_mnemonic_words(words);
+    }
+    if let Some(passphrase) = &options.passphrase {
+        test_suite = test_suite.with_passphrase(passphrase.clone());
+    }
+    if let Some(account) = options.account_index {
+        test_suite = test_suite.with_account_index(account);
+    }
+    if let Some(scheme) = options.address_scheme {
+        test_suite = test_suite.with_address_scheme(scheme);
+    }
+    if let Some(path) = &options.derivation_path {
+        test_suite = test_suite.with_derivation_path(path.clone());
+    }
+    if options.skip_faucet == Some(true) {
+        test_suite = test_suite.without_faucet("requested by the caller");
+    }
+    if let Some(scenario) = scenario {
+        test_suite = scenario.apply(test_suite);
+    }
+    test_suite
+}
+
+/// Runs a suite built by `build_suite`, settles the pool and saves the result.
+async fn finish_run(
+    data: &AppState,
+    options: &TestOptions,
+    wallet: Option<PooledWallet>,
+    test_suite: TestSuite,
+    triggered_by: String,
+) -> TestResult {
+    data.metrics.record_run_started();
+
     // Run the test and get the result - always returns a TestResult now
-    let result = test_suite.run().await;
-    let success = result.success;
-    
+    let mut result = test_suite.run_detached().await;
+    result.triggered_by = Some(triggered_by);
+    data.metrics.record_run_finished(result.success, result.inconclusive);
+
+    let pooled = data.config.pool.enabled && options.uses_default_wallet();
+    let balance = match result.network {
+        Some(network) if pooled => {
+            let address = wallet.as_ref().map_or(&result.btc_address, |wallet| &wallet.btc_address);
+            wallet_pool::current_balance(&data.config.suite, network, address).await
+        }
+        _ => None,
+    };
+    let db = data.db.lock().unwrap();
+    if pooled {
+        if let Err(e) = wallet_pool::settle(&db, &data.config.pool, wallet.as_ref(), balance, &mut result) {
+            log::error!("Failed to return wallet to the pool: {}", e);
+        }
+    }
+
     // Save the result to the database - ALL results are saved now
-    let db_result = data.db.lock().unwrap().save_result(&result);
-    if let Err(e) = db_result {
-        eprintln!("Failed to save test result to database: {}", e);
+    if let Err(e) = db.save_result(&result) {
+        log::error!("Failed to save test result to database: {}", e);
+    }
+    result
+}
+
+/// Stores a resumable run's checkpoints through a connection of its own, as
+/// the suite runs on a blocking thread.
+fn checkpoint_sink(config: &Config, triggered_by: String) -> CheckpointSink {
+    let db = config.open_database();
+    Box::new(move |checkpoint| match &db {
+        Ok(db) => {
+            if let Err(e) = db.save_scenario_checkpoint(checkpoint, &triggered_by) {
+                log::error!("Failed to checkpoint run {}: {}", checkpoint.run_id, e);
+            }
+        }
+        Err(e) => log::error!("Failed to open the database for checkpoints: {}", e),
+    })
+}
+
+/// Runs a resumable scenario to the end and drops its checkpoint once the
+/// result is saved.
+async fn run_resumable(
+    data: &AppState,
+    options: &TestOptions,
+    test_suite: TestSuite,
+    triggered_by: String,
+) {
+    let result = finish_run(data, options, None, test_suite, triggered_by).await;
+    if let Err(e) = data.db.lock().unwrap().delete_scenario_checkpoint(&result.id) {
+        log::error!("Failed to delete the checkpoint of run {}: {}", result.id, e);
+    }
+    log::info!("Resumable run {} finished: {}", result.id, result.details);
+}
+
+/// Picks up resumable scenario runs a previous process left unfinished.
+fn resume_scenario_runs(data: &web::Data<AppState>) {
+    let checkpoints = match data.db.lock().unwrap().get_scenario_checkpoints() {
+        Ok(checkpoints) => checkpoints,
+        Err(e) => {
+            log::error!("Failed to load scenario checkpoints: {}", e);
+            return;
+        }
+    };
+
+    for checkpoint in checkpoints {
+        let run_id = checkpoint.run_id.clone();
+        let triggered_by = checkpoint.triggered_by.clone().unwrap_or_default();
+        let options = TestOptions {
+            network: Some(checkpoint.network),
+            scenario: Some(checkpoint.scenario.clone()),
+            ..TestOptions::default()
+        };
+        log::info!(
+            "Resuming scenario '{}' run {} at step {}",
+            checkpoint.scenario,
+            run_id,
+            checkpoint.next_step + 1
+        );
+        match TestSuite::resume(data.config.suite.clone(), checkpoint) {
+            Ok(test_suite) => {
+                let test_suite =
+                    test_suite.with_checkpoints(checkpoint_sink(&data.config, triggered_by.clone()));
+                // Admitted before the restart, so only the slot is taken again
+                let permit = data.limiter.resume(&triggered_by);
+                let data = data.clone();
+                actix_web::rt::spawn(async move {
+                    let _permit = permit;
+                    run_resumable(&data, &options, test_suite, triggered_by).await;
+                });
+            }
+            Err(e) => {
+                log::error!("Dropping run {}, which can't be resumed: {}", run_id, e);
+                if let Err(e) = data.db.lock().unwrap().delete_scenario_checkpoint(&run_id) {
+                    log::error!("Failed to delete the checkpoint of run {}: {}", run_id, e);
+                }
+            }
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/scenario-runs",
+    tag = "runs",
+    responses((status = 200, description = "Resumable scenario runs still in progress, with their checkpoints", body = ApiResponse<Vec<ScenarioCheckpoint>>))
+)]
+async fn get_scenario_runs(caller: Caller, data: web::Data<AppState>) -> impl Responder {
+    match data.db.lock().unwrap().get_scenario_checkpoints() {
+        Ok(mut checkpoints) => {
+            if !caller.is_admin() {
+                checkpoints.iter_mut().for_each(ScenarioCheckpoint::redact_secrets);
+            }
+            let response = ApiResponse {
+                success: true,
+                message: format!("Found {} scenario runs in progress", checkpoints.len()),
+                data: Some(checkpoints),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<Vec<ScenarioCheckpoint>>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/matrix-runs",
+    tag = "runs",
+    request_body = MatrixRequest,
+    responses(
+        (status = 202, description = "Grid accepted; poll `GET /matrix-runs/{id}` for progress", body = ApiResponse<MatrixRun>),
+        (status = 400, description = "The grid is too large or its options are not allowed", body = ApiResponse<MatrixRun>)
+    )
+)]
+async fn create_matrix_run(
+    request: web::Json<MatrixRequest>,
+    caller: Caller,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let network = request.network.unwrap_or(data.config.suite.network);
+    let loans = request.expand(&data.config.suite.loan);
+    let allowed = data
+        .config
+        .suite
+        .check_network(network)
+        .and_then(|_| matrix::check_cells(&loans, data.config.matrix.max_cells as usize));
+    if let Err(message) = allowed {
+        let response = ApiResponse {
+            success: false,
+            message,
+            data: None::<MatrixRun>,
+            timestamp: Utc::now().to_rfc3339(),
+        };
+        return HttpResponse::BadRequest().json(response);
+    }
+
+    let concurrency = request
+        .concurrency
+        .unwrap_or(data.config.matrix.max_concurrency)
+        .clamp(1, data.config.matrix.max_concurrency);
+    let run = matrix::new_run(
+        caller.name,
+        network,
+        concurrency,
+        request.skip_faucet.unwrap_or(false),
+        loans,
+    );
+    if let Err(e) = data.db.lock().unwrap().create_matrix_run(&run) {
+        let response = ApiResponse {
+            success: false,
+            message: format!("Database error: {}", e),
+            data: None::<MatrixRun>,
+            timestamp: Utc::now().to_rfc3339(),
+        };
+        return HttpResponse::InternalServerError().json(response);
+    }
+
+    log::info!(
+        "Starting matrix run {} with {} cells, {} at a time",
+        run.id,
+        run.cells.len(),
+        run.concurrency
+    );
+    actix_web::rt::spawn(matrix::execute(data.clone(), run.clone()));
+
+    let response = ApiResponse {
+        success: true,
+        message: format!("Matrix run started with {} cells", run.cells.len()),
+        data: Some(run),
+        timestamp: Utc::now().to_rfc3339(),
+    };
+    HttpResponse::Accepted().json(response)
+}
+
+#[utoipa::path(
+    get,
+    path = "/matrix-runs",
+    tag = "runs",
+    responses((status = 200, description = "Matrix runs, newest first", body = ApiResponse<Vec<MatrixRun>>))
+)]
+async fn get_matrix_runs(data: web::Data<AppState>) -> impl Responder {
+    match data.db.lock().unwrap().get_matrix_runs() {
+        Ok(runs) => {
+            let response = ApiResponse {
+                success: true,
+                message: format!("Found {} matrix runs", runs.len()),
+                data: Some(runs),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<Vec<MatrixRun>>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct MatrixReportQuery {
+    /// `json` (default) or `markdown` for a pass/fail table
+    format: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/matrix-runs/{id}",
+    tag = "runs",
+    params(("id" = String, Path, description = "Matrix run id"), MatrixReportQuery),
+    responses(
+        (status = 200, description = "The grid with each cell's outcome", body = ApiResponse<MatrixRun>),
+        (status = 404, description = "No such matrix run", body = ApiResponse<MatrixRun>)
+    )
+)]
+async fn get_matrix_run(
+    path: web::Path<String>,
+    query: web::Query<MatrixReportQuery>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    match data.db.lock().unwrap().get_matrix_run(&id) {
+        Ok(Some(run)) if query.format.as_deref() == Some("markdown") => HttpResponse::Ok()
+            .content_type("text/markdown; charset=utf-8")
+            .body(run.to_markdown()),
+        Ok(Some(run)) => {
+            let response = ApiResponse {
+                success: true,
+                message: format!(
+                    "{} of {} cells passed",
+                    run.summary.passed, run.summary.total
+                ),
+                data: Some(run),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Ok(None) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Matrix run with ID {} not found", id),
+                data: None::<MatrixRun>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::NotFound().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<MatrixRun>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/load-runs",
+    tag = "runs",
+    request_body = LoadRequest,
+    responses(
+        (status = 202, description = "Load run accepted; poll `GET /load-runs/{id}` for its report", body = ApiResponse<LoadRun>),
+        (status = 400, description = "Too many suites, too long a ramp-up or options that are not allowed", body = ApiResponse<LoadRun>)
+    )
+)]
+async fn create_load_run(
+    request: web::Json<LoadRequest>,
+    caller: Caller,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let network = request.network.unwrap_or(data.config.suite.network);
+    let limits = &data.config.load;
+    // The stored baseline only stands for runs of the same flow
+    let baseline = match &request.baseline_id {
+        Some(id) => data.db.lock().unwrap().get_load_run(id),
+        None => data.db.lock().unwrap().get_load_baseline().map(|baseline| {
+            baseline.filter(|baseline| baseline.scenario == request.scenario)
+        }),
+    };
+    let allowed = data
+        .config
+        .suite
+        .check_network(network)
+        .and_then(|_| {
+            if request.suites == 0 || request.suites > limits.max_suites {
+                Err(format!(
+                    "suites must be between 1 and {} (load.max_suites)",
+                    limits.max_suites
+                ))
+            } else if request.ramp_up_secs > limits.max_ramp_up_secs {
+                Err(format!(
+                    "ramp_up_secs may be at most {} (load.max_ramp_up_secs)",
+                    limits.max_ramp_up_secs
+                ))
+            } else {
+                Ok(())
+            }
+        })
+        .and_then(|_| {
+            request
+                .scenario
+                .as_deref()
+                .map(|name| NamedScenario::find(&data.config.suite, name))
+                .transpose()
+        })
+        .and_then(|scenario| match &scenario {
+            Some(NamedScenario::File(file)) if file.definition.resumable => {
+                Err("Resumable scenarios can't be load tested".to_string())
+            }
+            _ => Ok(scenario),
+        })
+        .and_then(|scenario| match (&request.baseline_id, baseline) {
+            (_, Err(e)) => Err(format!("Database error: {}", e)),
+            (Some(id), Ok(None)) => Err(format!("Load run with ID {} not found", id)),
+            (Some(id), Ok(Some(run))) if run.report.is_none() => {
+                Err(format!("Load run {} has no report to compare with", id))
+            }
+            (_, Ok(baseline)) => Ok((scenario, baseline)),
+        });
+    let (scenario, baseline) = match allowed {
+        Ok(allowed) => allowed,
+        Err(message) => {
+            let response = ApiResponse {
+                success: false,
+                message,
+                data: None::<LoadRun>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            return HttpResponse::BadRequest().json(response);
+        }
+    };
+
+    let run = load::new_run(caller.name, network, &request);
+    if let Err(e) = data.db.lock().unwrap().create_load_run(&run) {
+        let response = ApiResponse {
+            success: false,
+            message: format!("Database error: {}", e),
+            data: None::<LoadRun>,
+            timestamp: Utc::now().to_rfc3339(),
+        };
+        return HttpResponse::InternalServerError().json(response);
+    }
+
+    log::info!(
+        "Starting load run {} with {} suites over {}s",
+        run.id,
+        run.suites.len(),
+        run.ramp_up_secs
+    );
+    let mut message = format!("Load run started with {} suites", run.suites.len());
+    if let Some(baseline) = &baseline {
+        message.push_str(&format!(", compared with {}", baseline.id));
+    }
+    let max_concurrent = data.config.limits.max_concurrent_runs;
+    if max_concurrent > 0 && request.suites > max_concurrent {
+        message.push_str(&format!(
+            "; limits.max_concurrent_runs lets only {} run at once",
+            max_concurrent
+        ));
+    }
+    actix_web::rt::spawn(load::execute(data.clone(), run.clone(), scenario, baseline));
+
+    let response = ApiResponse {
+        success: true,
+        message,
+        data: Some(run),
+        timestamp: Utc::now().to_rfc3339(),
+    };
+    HttpResponse::Accepted().json(response)
+}
+
+#[utoipa::path(
+    get,
+    path = "/load-runs",
+    tag = "runs",
+    responses((status = 200, description = "Load runs, newest first", body = ApiResponse<Vec<LoadRun>>))
+)]
+async fn get_load_runs(data: web::Data<AppState>) -> impl Responder {
+    match data.db.lock().unwrap().get_load_runs() {
+        Ok(runs) => {
+            let response = ApiResponse {
+                success: true,
+                message: format!("Found {} load runs", runs.len()),
+                data: Some(runs),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<Vec<LoadRun>>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/load-runs/{id}",
+    tag = "runs",
+    params(("id" = String, Path, description = "Load run id"), MatrixReportQuery),
+    responses(
+        (status = 200, description = "The suites and, once they have finished, the latency report", body = ApiResponse<LoadRun>),
+        (status = 404, description = "No such load run", body = ApiResponse<LoadRun>)
+    )
+)]
+async fn get_load_run(
+    path: web::Path<String>,
+    query: web::Query<MatrixReportQuery>,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    match data.db.lock().unwrap().get_load_run(&id) {
+        Ok(Some(run)) if query.format.as_deref() == Some("markdown") => HttpResponse::Ok()
+            .content_type("text/markdown; charset=utf-8")
+            .body(run.to_markdown()),
+        Ok(Some(run)) => {
+            let message = match &run.report {
+                Some(report) => match &report.comparison {
+                    Some(comparison) if comparison.regressed => format!(
+                        "{} of {} suites passed; regressed against {}",
+                        report.passed, report.total, comparison.baseline_id
+                    ),
+                    _ => format!("{} of {} suites passed", report.passed, report.total),
+                },
+                None => "Load run in progress".to_string(),
+            };
+            let response = ApiResponse {
+                success: true,
+                message,
+                data: Some(run),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Ok(None) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Load run with ID {} not found", id),
+                data: None::<LoadRun>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::NotFound().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<LoadRun>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/load-runs/{id}/baseline",
+    tag = "runs",
+    params(("id" = String, Path, description = "Load run id")),
+    responses(
+        (status = 200, description = "Later load runs compare with this one by default", body = ApiResponse<Object>),
+        (status = 404, description = "No finished load run with this id", body = ApiResponse<Object>)
+    )
+)]
+async fn set_load_baseline(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+    let id = path.into_inner();
+    match data.db.lock().unwrap().set_load_baseline(&id) {
+        Ok(true) => {
+            let response = ApiResponse {
+                success: true,
+                message: format!("Load run {} is now the baseline", id),
+                data: None::<()>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Ok(false) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("No finished load run with ID {}", id),
+                data: None::<()>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::NotFound().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<()>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/scenarios",
+    tag = "runs",
+    responses((status = 200, description = "Negative scenarios runs can name", body = ApiResponse<Vec<NegativeScenario>>))
+)]
+async fn get_scenarios(data: web::Data<AppState>) -> impl Responder {
+    let scenarios = negative::all(&data.config.suite);
+    let response = ApiResponse {
+        success: true,
+        message: format!("Found {} scenarios", scenarios.len()),
+        data: Some(scenarios),
+        timestamp: Utc::now().to_rfc3339(),
+    };
+    HttpResponse::Ok().json(response)
+}
+
+#[utoipa::path(
+    get,
+    path = "/scenario-files",
+    tag = "runs",
+    responses((status = 200, description = "Scenario files in `suite.scenarios_dir`; files that fail to load are listed in the message", body = ApiResponse<Vec<ScenarioFile>>))
+)]
+async fn get_scenario_files(data: web::Data<AppState>) -> impl Responder {
+    let (scenarios, problems) = scenario::load_all(&data.config.suite.scenarios_dir);
+    let mut message = format!(
+        "Found {} scenario files in {}",
+        scenarios.len(),
+        data.config.suite.scenarios_dir
+    );
+    if !problems.is_empty() {
+        message.push_str(&format!("; {} failed to load: {}", problems.len(), problems.join("; ")));
     }
-    
-    // Create and return the response
     let response = ApiResponse {
-        success,
-        message: if success {
-            "Test completed successfully".to_string()
-        } else {
-            format!("Test failed: {}", result.details)
-        },
-        data: Some(result),
+        success: true,
+        message,
+        data: Some(scenarios),
         timestamp: Utc::now().to_rfc3339(),
     };
-    
-    // Return appropriate HTTP status based on test success
-    if success {
-        HttpResponse::Ok().json(response)
-    } else {
-        HttpResponse::Ok().json(response) // Still return 200 OK, but with success: false
-    }
+    HttpResponse::Ok().json(response)
 }
-async fn get_all_results(data: web::Data<AppState>) -> impl Responder {
-    match data.db.lock().unwrap().get_all_results() {
-        Ok(results) => {
+
+#[utoipa::path(
+    get,
+    path = "/results",
+    tag = "results",
+    params(ResultFilter),
+    responses((status = 200, description = "Matching results, newest first", body = ApiResponse<Vec<TestResult>>))
+)]
+async fn get_all_results(
+    filter: web::Query<ResultFilter>,
+    caller: Caller,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    match data.db.lock().unwrap().get_all_results(&filter) {
+        Ok(mut results) => {
+            if !caller.is_admin() {
+                results.iter_mut().for_each(TestResult::redact_secrets);
+            }
             let response = ApiResponse {
                 success: true,
                 message: format!("Found {} test results", results.len()),
@@ -106,10 +962,56 @@ async fn get_all_results(data: web::Data<AppState>) -> impl Responder {
     }
 }
 
-async fn get_result(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+#[utoipa::path(
+    get,
+    path = "/results/cli-versions",
+    tag = "results",
+    responses((status = 200, description = "Pass/fail counts per CLI version", body = ApiResponse<Vec<CliVersionSummary>>))
+)]
+async fn get_cli_version_summary(data: web::Data<AppState>) -> impl Responder {
+    match data.db.lock().unwrap().get_cli_version_summary() {
+        Ok(summaries) => {
+            let response = ApiResponse {
+                success: true,
+                message: format!("Found {} CLI versions", summaries.len()),
+                data: Some(summaries),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<Vec<CliVersionSummary>>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/results/{id}",
+    tag = "results",
+    params(("id" = String, Path, description = "Run id")),
+    responses(
+        (status = 200, description = "The result", body = ApiResponse<TestResult>),
+        (status = 404, description = "No such run", body = ApiResponse<TestResult>)
+    )
+)]
+async fn get_result(
+    path: web::Path<String>,
+    caller: Caller,
+    data: web::Data<AppState>,
+) -> impl Responder {
     let id = path.into_inner();
     match data.db.lock().unwrap().get_result(&id) {
-        Ok(Some(result)) => {
+        Ok(Some(mut result)) => {
+            if !caller.is_admin() {
+                result.redact_secrets();
+            }
             let response = ApiResponse {
                 success: true,
                 message: "Test result found".to_string(),
@@ -139,6 +1041,260 @@ async fn get_result(path: web::Path<String>, data: web::Data<AppState>) -> impl
     }
 }
 
+/// `GET /results/{id}/diff/last-passing` compares a run with the most recent
+/// passing run before it.
+const LAST_PASSING: &str = "last-passing";
+
+#[utoipa::path(
+    get,
+    path = "/results/{id}/diff/{other}",
+    tag = "results",
+    params(
+        ("id" = String, Path, description = "Run id"),
+        ("other" = String, Path, description = "Run id to compare with, or `last-passing` for the most recent passing run of the same flow and network before it")
+    ),
+    responses(
+        (status = 200, description = "Diverged steps, duration changes, differing options and normalized CLI output diffs", body = ApiResponse<RunDiff>),
+        (status = 404, description = "No such run, or no passing run to compare with", body = ApiResponse<RunDiff>)
+    )
+)]
+async fn get_result_diff(
+    path: web::Path<(String, String)>,
+    caller: Caller,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let (id, other) = path.into_inner();
+    let not_found = |message: String| {
+        HttpResponse::NotFound().json(ApiResponse {
+            success: false,
+            message,
+            data: None::<RunDiff>,
+            timestamp: Utc::now().to_rfc3339(),
+        })
+    };
+    let db_error = |e: rusqlite::Error| {
+        HttpResponse::InternalServerError().json(ApiResponse {
+            success: false,
+            message: format!("Database error: {}", e),
+            data: None::<RunDiff>,
+            timestamp: Utc::now().to_rfc3339(),
+        })
+    };
+
+    // Only the lookups hold the database: reading both logs and diffing them
+    // takes a while and would stall every running suite's writes
+    let runs = {
+        let db = data.db.lock().unwrap();
+        db.get_result(&id).and_then(|a| {
+            let Some(a) = a else { return Ok(None) };
+            let b = if other == LAST_PASSING {
+                db.get_last_passing_before(&a)?
+            } else {
+                db.get_result(&other)?
+            };
+            Ok(Some((a, b)))
+        })
+    };
+    let (a, b) = match runs {
+        Ok(Some((a, Some(b)))) => (a, b),
+        Ok(Some((_, None))) if other == LAST_PASSING => {
+            return not_found(format!("No passing run of the same flow before {}", id))
+        }
+        Ok(Some((_, None))) => return not_found(format!("Test result with ID {} not found", other)),
+        Ok(None) => return not_found(format!("Test result with ID {} not found", id)),
+        Err(e) => return db_error(e),
+    };
+
+    let state = data.clone();
+    let compared = web::block(move || {
+        let a_events = load_events(&state, &a, &caller)?;
+        let b_events = load_events(&state, &b, &caller)?;
+        Ok::<_, rusqlite::Error>(diff::diff(&a, &a_events, &b, &b_events))
+    })
+    .await;
+    match compared {
+        Ok(Ok(run_diff)) => {
+            let message = match run_diff.first_divergence {
+                Some(step) => format!(
+                    "{} and {} first diverge at step {}",
+                    run_diff.a.id, run_diff.b.id, step
+                ),
+                None => format!("{} and {} ran the same steps alike", run_diff.a.id, run_diff.b.id),
+            };
+            let response = ApiResponse {
+                success: true,
+                message,
+                data: Some(run_diff),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Ok(Err(e)) => db_error(e),
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Failed to compare runs: {}", e),
+                data: None::<RunDiff>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+/// A run's log events, masked like `read_logs`. The artifact is read without
+/// the database; only rows from before the artifact store take the lock.
+fn load_events(data: &AppState, result: &TestResult, caller: &Caller) -> rusqlite::Result<Vec<LogEvent>> {
+    let store = ArtifactStore::new(&data.config.server.artifacts_dir, data.config.server.max_log_bytes);
+    let content = match store.read_logs(&result.id) {
+        Ok(Some(content)) => Some(content),
+        Ok(None) => data.db.lock().unwrap().get_logs(&result.id)?,
+        Err(e) => return Err(rusqlite::Error::ToSqlConversionFailure(Box::new(e))),
+    };
+    Ok(content
+        .map(|content| events::from_json_lines(&mask_secrets(content, result, caller), result.timestamp))
+        .unwrap_or_default())
+}
+
+/// Reads a run's log, masking its mnemonic and passphrase for callers that may
+/// not see secrets. Runs no longer log either, but older runs did.
+fn read_logs(db: &Database, id: &str, caller: &Caller) -> rusqlite::Result<Option<String>> {
+    let mut content = match db.get_logs(id)? {
+        Some(content) => content,
+        None => return Ok(None),
+    };
+    if caller.is_admin() {
+        return Ok(Some(content));
+    }
+    if let Some(result) = db.get_result(id)? {
+        content = mask_secrets(content, &result, caller);
+    }
+    Ok(Some(content))
+}
+
+/// Masks `result`'s mnemonic and passphrase in `content` unless `caller` may
+/// see secrets.
+fn mask_secrets(mut content: String, result: &TestResult, caller: &Caller) -> String {
+    if caller.is_admin() {
+        return content;
+    }
+    let secrets = std::iter::once(result.mnemonic.as_str()).chain(result.passphrase.as_deref());
+    for secret in secrets.filter(|secret| !secret.is_empty()) {
+        content = content.replace(secret, "***");
+    }
+    content
+}
+
+#[utoipa::path(
+    get,
+    path = "/results/{id}/events",
+    tag = "results",
+    params(("id" = String, Path, description = "Run id"), EventFilter),
+    responses(
+        (status = 200, description = "The run's log events", body = ApiResponse<Vec<LogEvent>>),
+        (status = 404, description = "No such run", body = ApiResponse<Vec<LogEvent>>)
+    )
+)]
+async fn get_result_events(
+    path: web::Path<String>,
+    filter: web::Query<EventFilter>,
+    caller: Caller,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    match read_logs(&data.db.lock().unwrap(), &id, &caller) {
+        Ok(Some(content)) => {
+            let events: Vec<LogEvent> = events::from_json_lines(&content, Utc::now())
+                .into_iter()
+                .filter(|event| filter.matches(event))
+                .collect();
+            let response = ApiResponse {
+                success: true,
+                message: format!("Found {} log events", events.len()),
+                data: Some(events),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Ok(None) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Test result with ID {} not found", id),
+                data: None::<Vec<LogEvent>>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::NotFound().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<Vec<LogEvent>>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/results/{id}/logs",
+    tag = "results",
+    params(("id" = String, Path, description = "Run id"), LogRange),
+    responses(
+        (status = 200, description = "A slice of the run's JSON-lines log", body = ApiResponse<LogChunk>),
+        (status = 404, description = "No such run", body = ApiResponse<LogChunk>)
+    )
+)]
+async fn get_result_logs(
+    path: web::Path<String>,
+    range: web::Query<LogRange>,
+    caller: Caller,
+    data: web::Data<AppState>,
+) -> impl Responder {
+    let id = path.into_inner();
+    match read_logs(&data.db.lock().unwrap(), &id, &caller) {
+        Ok(Some(content)) => {
+            let chunk = artifacts::slice_logs(&content, &range);
+            let response = ApiResponse {
+                success: true,
+                message: format!("Returning {} of {} log bytes", chunk.length, chunk.total_bytes),
+                data: Some(chunk),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Ok(None) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Test result with ID {} not found", id),
+                data: None::<LogChunk>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::NotFound().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<LogChunk>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/last-successful-mnemonic",
+    tag = "results",
+    responses(
+        (status = 200, description = "Wallet of the most recent passing run (admin only)", body = ApiResponse<Object>),
+        (status = 404, description = "No passing runs yet", body = ApiResponse<Object>)
+    )
+)]
 async fn get_last_successful_mnemonic(data: web::Data<AppState>) -> impl Responder {
     match data.db.lock().unwrap().get_last_successful_test() {
         Ok(Some(result)) => {
@@ -147,6 +1303,9 @@ async fn get_last_successful_mnemonic(data: web::Data<AppState>) -> impl Respond
                 message: "Last successful test found".to_string(),
                 data: Some(json!({
                     "mnemonic": result.mnemonic,
+                    "passphrase": result.passphrase,
+                    "account_index": result.account_index,
+                    "derivation_path": result.derivation_path,
                     "btc_address": result.btc_address,
                     "lava_pubkey": result.lava_pubkey,
                     "timestamp": result.timestamp
@@ -176,51 +1335,355 @@ async fn get_last_successful_mnemonic(data: web::Data<AppState>) -> impl Respond
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/wallets",
+    tag = "wallets",
+    responses((status = 200, description = "Pooled wallets; mnemonics are masked for read-only callers", body = ApiResponse<Vec<PooledWallet>>))
+)]
+async fn get_wallets(caller: Caller, data: web::Data<AppState>) -> impl Responder {
+    match data.db.lock().unwrap().get_wallets() {
+        Ok(mut wallets) => {
+            if !caller.is_admin() {
+                wallets.iter_mut().for_each(PooledWallet::redact_secrets);
+            }
+            let response = ApiResponse {
+                success: true,
+                message: format!("Found {} pooled wallets", wallets.len()),
+                data: Some(wallets),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<Vec<PooledWallet>>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+struct NewWallet {
+    mnemonic: String,
+    /// Defaults to `suite.network`
+    network: Option<BtcNetwork>,
+    /// Known balance; unset means the first run that leases it tops it up
+    balance_sats: Option<u64>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/wallets",
+    tag = "wallets",
+    request_body = NewWallet,
+    responses(
+        (status = 200, description = "The wallet joined the pool", body = ApiResponse<PooledWallet>),
+        (status = 400, description = "The mnemonic is invalid", body = ApiResponse<PooledWallet>),
+        (status = 409, description = "The mnemonic is already pooled", body = ApiResponse<PooledWallet>)
+    )
+)]
+async fn add_wallet(body: web::Json<NewWallet>, data: web::Data<AppState>) -> impl Responder {
+    let suite = &data.config.suite;
+    let wallet = match wallet_pool::import(
+        &body.mnemonic,
+        body.network.unwrap_or(suite.network),
+        suite.address_scheme,
+        suite.account_index,
+        body.balance_sats,
+    ) {
+        Ok(wallet) => wallet,
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: e.to_string(),
+                data: None::<PooledWallet>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            return HttpResponse::BadRequest().json(response);
+        }
+    };
+
+    match data.db.lock().unwrap().add_wallet(&wallet) {
+        Ok(()) => {
+            let response = ApiResponse {
+                success: true,
+                message: format!("Added wallet {} to the {} pool", wallet.id, wallet.network),
+                data: Some(wallet),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Err(rusqlite::Error::SqliteFailure(e, _))
+            if e.code == rusqlite::ErrorCode::ConstraintViolation =>
+        {
+            let response = ApiResponse {
+                success: false,
+                message: "This mnemonic is already in the pool".to_string(),
+                data: None::<PooledWallet>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Conflict().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<PooledWallet>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/wallets/{id}",
+    tag = "wallets",
+    params(("id" = String, Path, description = "Wallet id")),
+    responses(
+        (status = 200, description = "The wallet left the pool", body = ApiResponse<Object>),
+        (status = 404, description = "No such wallet", body = ApiResponse<Object>)
+    )
+)]
+async fn delete_wallet(path: web::Path<String>, data: web::Data<AppState>) -> impl Responder {
+    let id = path.into_inner();
+    match data.db.lock().unwrap().delete_wallet(&id) {
+        Ok(true) => {
+            let response = ApiResponse {
+                success: true,
+                message: format!("Removed wallet {} from the pool", id),
+                data: None::<()>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Ok(false) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Wallet with ID {} not found", id),
+                data: None::<()>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::NotFound().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<()>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct AuditQuery {
+    /// Maximum entries to return (default 100)
+    limit: Option<u32>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/audit",
+    tag = "server",
+    params(AuditQuery),
+    responses((status = 200, description = "Recent API requests (admin only)", body = ApiResponse<Vec<AuditEntry>>))
+)]
+async fn get_audit_log(query: web::Query<AuditQuery>, data: web::Data<AppState>) -> impl Responder {
+    match data.db.lock().unwrap().get_audit_log(query.limit.unwrap_or(100)) {
+        Ok(entries) => {
+            let response = ApiResponse {
+                success: true,
+                message: format!("Found {} audit entries", entries.len()),
+                data: Some(entries),
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::Ok().json(response)
+        }
+        Err(e) => {
+            let response = ApiResponse {
+                success: false,
+                message: format!("Database error: {}", e),
+                data: None::<Vec<AuditEntry>>,
+                timestamp: Utc::now().to_rfc3339(),
+            };
+            HttpResponse::InternalServerError().json(response)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "server",
+    responses((status = 200, description = "Prometheus text exposition", body = String, content_type = "text/plain"))
+)]
+async fn get_metrics(data: web::Data<AppState>) -> impl Responder {
+    HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4")
+        .body(data.metrics.render())
+}
+
+#[utoipa::path(
+    get,
+    path = "/config",
+    tag = "server",
+    responses((status = 200, description = "Effective configuration with secrets redacted", body = ApiResponse<Object>))
+)]
+async fn get_config(data: web::Data<AppState>) -> impl Responder {
+    let response = ApiResponse {
+        success: true,
+        message: "Effective configuration".to_string(),
+        data: Some(data.config.redacted()),
+        timestamp: Utc::now().to_rfc3339(),
+    };
+    HttpResponse::Ok().json(response)
+}
+
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
+    let cli = Cli::parse();
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    if let Some(Command::HashToken { token }) = cli.command {
+        cli::hash_token(token);
+        return Ok(());
+    }
+    if let Some(Command::MockEsplora {
+        port,
+        balance_sats,
+        confirm_after_secs,
+        block_secs,
+    }) = cli.command
+    {
+        return esplora::serve_mock(port, balance_sats, confirm_after_secs, block_secs).await;
+    }
+
+    // Load configuration from file and environment
+    let config = match Config::load(cli.config.as_deref()) {
+        Ok(config) => config,
+        Err(e) => {
+            log::error!("Failed to load configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    match cli.command {
+        Some(Command::Run(args)) => std::process::exit(cli::run(config, args).await),
+        Some(Command::Serve) | None => serve(config).await,
+        Some(Command::HashToken { .. }) | Some(Command::MockEsplora { .. }) => {
+            unreachable!("handled before loading config")
+        }
+    }
+}
+
+async fn serve(config: Config) -> std::io::Result<()> {
     // Initialize database
-    let db_path = env::var("DATABASE_PATH").unwrap_or_else(|_| "data/test_results.db".to_string());
-    println!("Using database at: {}", db_path);
+    let db_path = config.server.database_path.clone();
+    log::info!("Using database at: {}", db_path);
     
-    let db = match Database::new(&db_path) {
+    let db = match config.open_database() {
         Ok(db) => db,
         Err(e) => {
-            eprintln!("Failed to initialize database: {}", e);
+            log::error!("Failed to initialize database: {}", e);
             std::process::exit(1);
         }
     };
     
+    match db.interrupt_matrix_runs() {
+        Ok(0) => {}
+        Ok(count) => log::warn!("Marked {} unfinished matrix runs as interrupted", count),
+        Err(e) => log::error!("Failed to close unfinished matrix runs: {}", e),
+    }
+    match db.interrupt_load_runs() {
+        Ok(0) => {}
+        Ok(count) => log::warn!("Marked {} unfinished load runs as interrupted", count),
+        Err(e) => log::error!("Failed to close unfinished load runs: {}", e),
+    }
+
+    if config.auth.tokens.is_empty() {
+        log::warn!("No API tokens configured; authentication is disabled");
+    }
+
+    let bind_address = config.bind_address();
+    let cli_path = config.suite.cli_path.clone();
+    let managed_cli = cli_binary::managed_path(&config.suite.cli);
+
+    // Seed the daily budget with runs already made today
+    let today = Utc::now().date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
+    let runs_today = db.count_results_since(today).unwrap_or(0);
+    let metrics = Arc::new(Metrics::default());
+    let limiter = Arc::new(RunLimiter::new(
+        config.limits.clone(),
+        Arc::clone(&metrics),
+        runs_today,
+    ));
+
     let app_state = web::Data::new(AppState {
         db: Mutex::new(db),
+        config,
+        metrics,
+        limiter,
     });
     
-    // Get host and port from environment or use defaults
-    let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-    let port = env::var("PORT").unwrap_or_else(|_| "8080".to_string());
-    let bind_address = format!("{}:{}", host, port);
-    
-    println!("Starting Borrower CLI Test Server on {}", bind_address);
+    resume_scenario_runs(&app_state);
+
+    log::info!("Starting Borrower CLI Test Server on {}", bind_address);
     
     // Ensure the CLI is executable before starting the server
-    let cli_path = "./loans-borrower-cli";
-    if std::path::Path::new(cli_path).exists() {
-        match std::process::Command::new("chmod").arg("+x").arg(cli_path).output() {
-            Ok(_) => println!("CLI permissions set"),
-            Err(e) => println!("Warning: Could not set CLI permissions: {}", e),
+    if let Some(path) = managed_cli {
+        log::info!("Using pinned CLI at {} (downloaded on first test run if missing)", path.display());
+    } else if std::path::Path::new(&cli_path).exists() {
+        match std::process::Command::new("chmod").arg("+x").arg(&cli_path).output() {
+            Ok(_) => log::info!("CLI permissions set"),
+            Err(e) => log::warn!("Could not set CLI permissions: {}", e),
         }
     } else {
-        println!("Warning: CLI not found at {}. It will be downloaded on first test run.", cli_path);
+        log::warn!("CLI not found at {}. Set suite.cli.version to download a pinned release.", cli_path);
     }
     
     HttpServer::new(move || {
         App::new()
             .app_data(app_state.clone())
+            .wrap(middleware::from_fn(auth::authenticate))
             .route("/", web::get().to(health_check))
             .route("/health", web::get().to(health_check))
             .route("/run-test", web::post().to(run_test))
+            .route("/scenarios", web::get().to(get_scenarios))
+            .route("/scenario-files", web::get().to(get_scenario_files))
+            .route("/scenario-runs", web::get().to(get_scenario_runs))
+            .route("/matrix-runs", web::post().to(create_matrix_run))
+            .route("/matrix-runs", web::get().to(get_matrix_runs))
+            .route("/matrix-runs/{id}", web::get().to(get_matrix_run))
+            .route("/load-runs", web::post().to(create_load_run))
+            .route("/load-runs", web::get().to(get_load_runs))
+            .route("/load-runs/{id}", web::get().to(get_load_run))
+            .route("/load-runs/{id}/baseline", web::put().to(set_load_baseline))
             .route("/results", web::get().to(get_all_results))
+            .route("/results/cli-versions", web::get().to(get_cli_version_summary))
             .route("/results/{id}", web::get().to(get_result))
+            .route("/results/{id}/events", web::get().to(get_result_events))
+            .route("/results/{id}/logs", web::get().to(get_result_logs))
+            .route("/results/{id}/diff/{other}", web::get().to(get_result_diff))
             .route("/last-successful-mnemonic", web::get().to(get_last_successful_mnemonic))
+            .route("/wallets", web::get().to(get_wallets))
+            .route("/wallets", web::post().to(add_wallet))
+            .route("/wallets/{id}", web::delete().to(delete_wallet))
+            .route("/config", web::get().to(get_config))
+            .route("/audit", web::get().to(get_audit_log))
+            .route("/metrics", web::get().to(get_metrics))
+            .route("/openapi.json", web::get().to(openapi::openapi_json))
+            .route("/docs", web::get().to(openapi::swagger_ui_redirect))
+            .route("/docs/{path:.*}", web::get().to(openapi::swagger_ui))
     })
     .bind(bind_address)?
     .run()
@@ -0,0 +1,438 @@
+use crate::config::LoanConfig;
+use crate::limits::ThrottleReason;
+use crate::network::BtcNetwork;
+use crate::{AppState, TestOptions};
+use actix_web::web;
+use chrono::Utc;
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::str::FromStr;
+use tokio::time::{sleep, Duration};
+use utoipa::ToSchema;
+
+/// Longest a cell waits between attempts to get a run slot or a pooled wallet.
+const MAX_WAIT_SECS: u64 = 30;
+
+/// Value lists for a grid of runs; every combination becomes one cell.
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct MatrixRequest {
+    /// LTV ratios in basis points; empty uses `suite.loan.ltv_ratio_bp`
+    #[serde(default)]
+    pub ltv_ratio_bp: Vec<u32>,
+    /// Loan durations in days; empty uses `suite.loan.duration_days`
+    #[serde(default)]
+    pub duration_days: Vec<u32>,
+    /// Loan amounts; empty uses `suite.loan.amount`
+    #[serde(default)]
+    pub amount: Vec<u64>,
+    /// Cells run at once; defaults to and is capped at `matrix.max_concurrency`
+    pub concurrency: Option<u32>,
+    /// Defaults to `suite.network`
+    pub network: Option<BtcNetwork>,
+    pub skip_faucet: Option<bool>,
+}
+
+impl MatrixRequest {
+    /// Every combination of the requested values, varying the amount fastest.
+    pub fn expand(&self, defaults: &LoanConfig) -> Vec<LoanConfig> {
+        let mut cells = Vec::new();
+        for ltv_ratio_bp in values_or(&self.ltv_ratio_bp, defaults.ltv_ratio_bp) {
+            for duration_days in values_or(&self.duration_days, defaults.duration_days) {
+                for amount in values_or(&self.amount, defaults.amount) {
+                    cells.push(LoanConfig {
+                        ltv_ratio_bp,
+                        duration_days,
+                        amount,
+                        ..defaults.clone()
+                    });
+                }
+            }
+        }
+        cells
+    }
+}
+
+/// Rejects grids larger than `max_cells` and cells no loan could have.
+pub fn check_cells(loans: &[LoanConfig], max_cells: usize) -> Result<(), String> {
+    if loans.len() > max_cells {
+        return Err(format!(
+            "The grid has {} cells; matrix.max_cells allows {}",
+            loans.len(),
+            max_cells
+        ));
+    }
+    let invalid = loans.iter().find(|loan| {
+        loan.ltv_ratio_bp == 0 || loan.ltv_ratio_bp > 10000 || loan.duration_days == 0 || loan.amount == 0
+    });
+    match invalid {
+        Some(loan) => Err(format!(
+            "Invalid cell: ltv_ratio_bp must be between 1 and 10000 and duration_days and amount at least 1, got {}, {} and {}",
+            loan.ltv_ratio_bp, loan.duration_days, loan.amount
+        )),
+        None => Ok(()),
+    }
+}
+
+fn values_or<T: Copy>(values: &[T], default: T) -> Vec<T> {
+    if values.is_empty() {
+        vec![default]
+    } else {
+        values.to_vec()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum CellStatus {
+    Pending,
+    Running,
+    Passed,
+    Failed,
+    Inconclusive,
+    /// Never run: the daily budget ran out or the server restarted
+    Skipped,
+}
+
+impl CellStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CellStatus::Pending => "pending",
+            CellStatus::Running => "running",
+            CellStatus::Passed => "passed",
+            CellStatus::Failed => "failed",
+            CellStatus::Inconclusive => "inconclusive",
+            CellStatus::Skipped => "skipped",
+        }
+    }
+}
+
+impl FromStr for CellStatus {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(CellStatus::Pending),
+            "running" => Ok(CellStatus::Running),
+            "passed" => Ok(CellStatus::Passed),
+            "failed" => Ok(CellStatus::Failed),
+            "inconclusive" => Ok(CellStatus::Inconclusive),
+            "skipped" => Ok(CellStatus::Skipped),
+            _ => Err(format!("Unknown cell status '{}'", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MatrixCell {
+    pub index: u32,
+    pub ltv_ratio_bp: u32,
+    pub duration_days: u32,
+    pub amount: u64,
+    pub status: CellStatus,
+    /// The run's stored result, once it has finished
+    pub result_id: Option<String>,
+    pub details: Option<String>,
+}
+
+/// Cell counts by outcome; `pending` includes running cells.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct MatrixSummary {
+    pub total: u32,
+    pub passed: u32,
+    pub failed: u32,
+    pub inconclusive: u32,
+    pub skipped: u32,
+    pub pending: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct MatrixRun {
+    pub id: String,
+    pub requested_by: String,
+    pub network: BtcNetwork,
+    pub concurrency: u32,
+    pub skip_faucet: bool,
+    pub created_at: String,
+    /// Unset while cells are still pending or running
+    pub finished_at: Option<String>,
+    pub summary: MatrixSummary,
+    pub cells: Vec<MatrixCell>,
+}
+
+impl MatrixRun {
+    pub fn summarize(&mut self) {
+        let mut summary = MatrixSummary {
+            total: self.cells.len() as u32,
+            ..MatrixSummary::default()
+        };
+        for cell in &self.cells {
+            match cell.status {
+                CellStatus::Pending | CellStatus::Running => summary.pending += 1,
+                CellStatus::Passed => summary.passed += 1,
+                CellStatus::Failed => summary.failed += 1,
+                CellStatus::Inconclusive => summary.inconclusive += 1,
+                CellStatus::Skipped => summary.skipped += 1,
+            }
+        }
+        self.summary = summary;
+    }
+
+    /// Renders the grid as a Markdown table: one row per LTV and duration,
+    /// one column per amount.
+    pub fn to_markdown(&self) -> String {
+        let mut amounts: Vec<u64> = self.cells.iter().map(|cell| cell.amount).collect();
+        amounts.sort_unstable();
+        amounts.dedup();
+        let mut rows: Vec<(u32, u32)> = self
+            .cells
+            .iter()
+            .map(|cell| (cell.ltv_ratio_bp, cell.duration_days))
+            .collect();
+        rows.sort_unstable();
+        rows.dedup();
+
+        let mut out = String::new();
+        let _ = writeln!(out, "# Matrix run {}\n", self.id);
+        let _ = writeln!(
+            out,
+            "{} cells on {}: {} passed, {} failed, {} inconclusive, {} skipped, {} pending\n",
+            self.summary.total,
+            self.network,
+            self.summary.passed,
+            self.summary.failed,
+            self.summary.inconclusive,
+            self.summary.skipped,
+            self.summary.pending
+        );
+        let _ = write!(out, "| LTV (bp) | Days |");
+        for amount in &amounts {
+            let _ = write!(out, " Amount {} |", amount);
+        }
+        let _ = write!(out, "\n| --- | --- |");
+        for _ in &amounts {
+            let _ = write!(out, " --- |");
+        }
+        out.push('\n');
+        for (ltv, days) in rows {
+            let _ = write!(out, "| {} | {} |", ltv, days);
+            for amount in &amounts {
+                let status = self
+                    .cells
+                    .iter()
+                    .find(|cell| {
+                        cell.ltv_ratio_bp == ltv && cell.duration_days == days && cell.amount == *amount
+                    })
+                    .map_or("", |cell| cell.status.as_str());
+                let _ = write!(out, " {} |", status);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Runs every cell of `run`, at most `run.concurrency` at a time, recording
+/// each outcome as it finishes.
+pub async fn execute(data: web::Data<AppState>, run: MatrixRun) {
+    let concurrency = run.concurrency.max(1) as usize;
+    stream::iter(run.cells.iter())
+        .for_each_concurrent(concurrency, |cell| run_cell(&data, &run, cell))
+        .await;
+
+    if let Err(e) = data.db.lock().unwrap().finish_matrix_run(&run.id) {
+        log::error!("Failed to finish matrix run {}: {}", run.id, e);
+    }
+    log::info!("Matrix run {} finished", run.id);
+}
+
+async fn run_cell(data: &AppState, run: &MatrixRun, cell: &MatrixCell) {
+    let update = |status: CellStatus, result_id: Option<&str>, details: Option<&str>| {
+        let db = data.db.lock().unwrap();
+        if let Err(e) = db.update_matrix_cell(&run.id, cell.index, status, result_id, details) {
+            log::error!("Failed to update cell {} of matrix run {}: {}", cell.index, run.id, e);
+        }
+    };
+
+    // Wait for a run slot like a client honouring Retry-After would
+    let _permit = loop {
+        match data.limiter.try_acquire(&run.requested_by) {
+            Ok(permit) => break permit,
+            Err(throttled) if throttled.reason == ThrottleReason::DailyBudget => {
+                update(CellStatus::Skipped, None, Some(&throttled.to_string()));
+                return;
+            }
+            Err(throttled) => sleep(Duration::from_secs(throttled.retry_after_secs.min(MAX_WAIT_SECS))).await,
+        }
+    };
+
+    let options = TestOptions {
+        skip_faucet: Some(run.skip_faucet),
+        network: Some(run.network),
+        ..TestOptions::default()
+    };
+    let wallet = loop {
+        match crate::lease_wallet(data, &options, run.network) {
+            Ok(wallet) => break wallet,
+            Err(_) => sleep(Duration::from_secs(MAX_WAIT_SECS)).await,
+        }
+    };
+
+    update(CellStatus::Running, None, None);
+    let loan = LoanConfig {
+        ltv_ratio_bp: cell.ltv_ratio_bp,
+        duration_days: cell.duration_days,
+        amount: cell.amount,
+        ..data.config.suite.loan.clone()
+    };
+    let result = crate::execute_run(
+        data,
+        &options,
+        run.network,
+        wallet,
+        Some(loan),
+        None,
+        format!("{} (matrix {})", run.requested_by, run.id),
+    )
+    .await;
+
+    let status = if result.success {
+        CellStatus::Passed
+    } else if result.inconclusive {
+        CellStatus::Inconclusive
+    } else {
+        CellStatus::Failed
+    };
+    update(status, Some(&result.id), Some(&result.details));
+}
+
+/// A new run with every cell pending.
+pub fn new_run(
+    requested_by: String,
+    network: BtcNetwork,
+    concurrency: u32,
+    skip_faucet: bool,
+    loans: Vec<LoanConfig>,
+) -> MatrixRun {
+    let cells = loans
+        .into_iter()
+        .enumerate()
+        .map(|(index, loan)| MatrixCell {
+            index: index as u32,
+            ltv_ratio_bp: loan.ltv_ratio_bp,
+            duration_days: loan.duration_days,
+            amount: loan.amount,
+            status: CellStatus::Pending,
+            result_id: None,
+            details: None,
+        })
+        .collect();
+    let mut run = MatrixRun {
+        id: uuid::Uuid::new_v4().to_string(),
+        requested_by,
+        network,
+        concurrency,
+        skip_faucet,
+        created_at: Utc::now().to_rfc3339(),
+        finished_at: None,
+        summary: MatrixSummary::default(),
+        cells,
+    };
+    run.summarize();
+    run
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(ltv_ratio_bp: Vec<u32>, duration_days: Vec<u32>, amount: Vec<u64>) -> MatrixRequest {
+        MatrixRequest {
+            ltv_ratio_bp,
+            duration_days,
+            amount,
+            concurrency: None,
+            network: None,
+            skip_faucet: None,
+        }
+    }
+
+    fn params(loans: &[LoanConfig]) -> Vec<(u32, u32, u64)> {
+        loans
+            .iter()
+            .map(|loan| (loan.ltv_ratio_bp, loan.duration_days, loan.amount))
+            .collect()
+    }
+
+    #[test]
+    fn expand_varies_the_amount_fastest() {
+        let cells = request(vec![4000, 5000], vec![4, 7], vec![2, 5]).expand(&LoanConfig::default());
+        assert_eq!(
+            params(&cells),
+            vec![
+                (4000, 4, 2),
+                (4000, 4, 5),
+                (4000, 7, 2),
+                (4000, 7, 5),
+                (5000, 4, 2),
+                (5000, 4, 5),
+                (5000, 7, 2),
+                (5000, 7, 5),
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_lists_use_the_configured_loan() {
+        let defaults = LoanConfig::default();
+        let cells = request(vec![], vec![], vec![]).expand(&defaults);
+        assert_eq!(
+            params(&cells),
+            vec![(defaults.ltv_ratio_bp, defaults.duration_days, defaults.amount)]
+        );
+
+        let cells = request(vec![], vec![7, 14], vec![]).expand(&defaults);
+        assert_eq!(
+            params(&cells),
+            vec![
+                (defaults.ltv_ratio_bp, 7, defaults.amount),
+                (defaults.ltv_ratio_bp, 14, defaults.amount),
+            ]
+        );
+    }
+
+    #[test]
+    fn check_cells_rejects_large_grids_and_impossible_loans() {
+        let defaults = LoanConfig::default();
+        let grid = request(vec![4000, 5000], vec![4], vec![2]).expand(&defaults);
+        assert!(check_cells(&grid, 2).is_ok());
+        assert!(check_cells(&grid, 1).unwrap_err().contains("max_cells"));
+
+        for (ltv, days, amount) in [(0, 4, 2), (10001, 4, 2), (5000, 0, 2), (5000, 4, 0)] {
+            let grid = request(vec![5000, ltv], vec![days], vec![amount]).expand(&defaults);
+            let error = check_cells(&grid, 10).unwrap_err();
+            assert!(error.starts_with("Invalid cell"), "{} {} {}: {}", ltv, days, amount, error);
+        }
+    }
+
+    #[test]
+    fn markdown_has_a_row_per_ltv_and_duration_and_a_column_per_amount() {
+        let loans = request(vec![5000, 4000], vec![4], vec![5, 2]).expand(&LoanConfig::default());
+        let mut run = new_run("ci".to_string(), BtcNetwork::Signet, 2, false, loans);
+        run.id = "grid".to_string();
+        run.cells[0].status = CellStatus::Passed;
+        run.cells[1].status = CellStatus::Failed;
+        run.cells[2].status = CellStatus::Skipped;
+        run.summarize();
+
+        assert_eq!(
+            run.to_markdown(),
+            "# Matrix run grid\n\n\
+             4 cells on signet: 1 passed, 1 failed, 0 inconclusive, 1 skipped, 1 pending\n\n\
+             | LTV (bp) | Days | Amount 2 | Amount 5 |\n\
+             | --- | --- | --- | --- |\n\
+             | 4000 | 4 | pending | skipped |\n\
+             | 5000 | 4 | failed | passed |\n"
+        );
+    }
+}
@@ -0,0 +1,79 @@
+use crate::limits::ThrottleReason;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+/// Process-wide counters, rendered in the Prometheus text format.
+#[derive(Debug, Default)]
+pub struct Metrics {
+    runs_started: AtomicU64,
+    runs_passed: AtomicU64,
+    runs_failed: AtomicU64,
+    runs_inconclusive: AtomicU64,
+    throttled_concurrency: AtomicU64,
+    throttled_rate_limit: AtomicU64,
+    throttled_daily_budget: AtomicU64,
+}
+
+impl Metrics {
+    pub fn record_run_started(&self) {
+        self.runs_started.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_run_finished(&self, success: bool, inconclusive: bool) {
+        if success {
+            self.runs_passed.fetch_add(1, Ordering::Relaxed);
+        } else if inconclusive {
+            self.runs_inconclusive.fetch_add(1, Ordering::Relaxed);
+        } else {
+            self.runs_failed.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    pub fn record_throttled(&self, reason: ThrottleReason) {
+        let counter = match reason {
+            ThrottleReason::Concurrency => &self.throttled_concurrency,
+            ThrottleReason::RateLimit => &self.throttled_rate_limit,
+            ThrottleReason::DailyBudget => &self.throttled_daily_budget,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn render(&self) -> String {
+        let mut out = String::new();
+        let counter = |out: &mut String, name: &str, help: &str, samples: &[(&str, &AtomicU64)]| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            for (labels, value) in samples {
+                let _ = writeln!(out, "{}{} {}", name, labels, value.load(Ordering::Relaxed));
+            }
+        };
+
+        counter(
+            &mut out,
+            "borrower_tester_runs_started_total",
+            "Test runs started.",
+            &[("", &self.runs_started)],
+        );
+        counter(
+            &mut out,
+            "borrower_tester_runs_finished_total",
+            "Test runs finished, by outcome.",
+            &[
+                ("{outcome=\"passed\"}", &self.runs_passed),
+                ("{outcome=\"failed\"}", &self.runs_failed),
+                ("{outcome=\"inconclusive\"}", &self.runs_inconclusive),
+            ],
+        );
+        counter(
+            &mut out,
+            "borrower_tester_runs_throttled_total",
+            "Run requests rejected by limits, by reason.",
+            &[
+                ("{reason=\"concurrency\"}", &self.throttled_concurrency),
+                ("{reason=\"rate_limit\"}", &self.throttled_rate_limit),
+                ("{reason=\"daily_budget\"}", &self.throttled_daily_budget),
+            ],
+        );
+        out
+    }
+}
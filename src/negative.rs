@@ -0,0 +1,153 @@
+use crate::config::SuiteConfig;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::process::Output;
+use utoipa::ToSchema;
+
+/// A contract id no deployment will ever have issued.
+pub const UNKNOWN_CONTRACT_ID: &str = "00000000-0000-0000-0000-000000000000";
+
+/// The CLI command a negative scenario runs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NegativeCommand {
+    BorrowInit,
+    BorrowRepay,
+}
+
+/// How the CLI is expected to fail.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ExpectedFailure {
+    /// Exact exit code; unset accepts any non-zero exit
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// Regex stderr must match
+    #[serde(default)]
+    pub stderr_pattern: Option<String>,
+}
+
+impl ExpectedFailure {
+    /// Explains how `output` differs from the expected failure.
+    pub fn check(&self, output: &Output) -> Result<(), String> {
+        let code = output.status.code();
+        if output.status.success() {
+            return Err("The CLI succeeded but was expected to fail".to_string());
+        }
+        if let Some(expected) = self.exit_code {
+            if code != Some(expected) {
+                return Err(format!(
+                    "Expected exit code {}, got {}",
+                    expected,
+                    code.map_or("none (killed by a signal)".to_string(), |code| code.to_string())
+                ));
+            }
+        }
+        if let Some(pattern) = &self.stderr_pattern {
+            let re = Regex::new(pattern)
+                .map_err(|e| format!("Invalid stderr pattern '{}': {}", pattern, e))?;
+            if !re.is_match(&String::from_utf8_lossy(&output.stderr)) {
+                return Err(format!("stderr does not match /{}/", pattern));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A CLI invocation that must be rejected, and how.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NegativeScenario {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub command: NegativeCommand,
+    /// Loan parameters replacing `suite.loan` for `borrow_init`
+    #[serde(default)]
+    pub ltv_ratio_bp: Option<u32>,
+    #[serde(default)]
+    pub duration_days: Option<u32>,
+    #[serde(default)]
+    pub amount: Option<u64>,
+    /// Contract to repay for `borrow_repay`; defaults to one that doesn't exist
+    #[serde(default)]
+    pub contract_id: Option<String>,
+    /// Call the faucet before the command; off for unfunded-wallet checks
+    #[serde(default)]
+    pub fund: bool,
+    pub expect: ExpectedFailure,
+}
+
+/// Scenarios shipped with the tester. Their stderr patterns are deliberately
+/// loose so wording changes in the CLI don't break them. None calls the
+/// faucet: argument checks don't need funds, and `unfunded-wallet` relies on
+/// an empty wallet.
+pub fn builtin() -> Vec<NegativeScenario> {
+    let scenario = |name: &str, description: &str, command, pattern: &str| NegativeScenario {
+        name: name.to_string(),
+        description: description.to_string(),
+        command,
+        ltv_ratio_bp: None,
+        duration_days: None,
+        amount: None,
+        contract_id: None,
+        fund: false,
+        expect: ExpectedFailure {
+            exit_code: None,
+            stderr_pattern: Some(pattern.to_string()),
+        },
+    };
+
+    vec![
+        scenario(
+            "repay-unknown-contract",
+            "Repaying a contract id that doesn't exist",
+            NegativeCommand::BorrowRepay,
+            r"(?i)not found|unknown|does not exist|no such|invalid",
+        ),
+        NegativeScenario {
+            ltv_ratio_bp: Some(10001),
+            ..scenario(
+                "ltv-above-max",
+                "Borrowing with an LTV above 100%",
+                NegativeCommand::BorrowInit,
+                r"(?i)ltv|ratio",
+            )
+        },
+        NegativeScenario {
+            amount: Some(0),
+            ..scenario(
+                "zero-amount",
+                "Borrowing an amount of zero",
+                NegativeCommand::BorrowInit,
+                r"(?i)amount",
+            )
+        },
+        scenario(
+            "unfunded-wallet",
+            "Borrowing from a wallet the faucet never funded",
+            NegativeCommand::BorrowInit,
+            r"(?i)insufficient|not enough|balance|funds?\b",
+        ),
+    ]
+}
+
+/// Built-in scenarios followed by those from `suite.negative_scenarios`; a
+/// configured scenario replaces a built-in one of the same name.
+pub fn all(config: &SuiteConfig) -> Vec<NegativeScenario> {
+    let mut scenarios: Vec<NegativeScenario> = builtin()
+        .into_iter()
+        .filter(|scenario| {
+            !config
+                .negative_scenarios
+                .iter()
+                .any(|configured| configured.name == scenario.name)
+        })
+        .collect();
+    scenarios.extend(config.negative_scenarios.iter().cloned());
+    scenarios
+}
+
+pub fn find(config: &SuiteConfig, name: &str) -> Option<NegativeScenario> {
+    all(config).into_iter().find(|scenario| scenario.name == name)
+}
@@ -0,0 +1,80 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+use utoipa::ToSchema;
+
+/// The Bitcoin network a run targets. It decides how addresses are encoded,
+/// the coin type in derivation paths, the CLI's network flag and which faucet
+/// funds the wallet.
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize,
+    ToSchema, ValueEnum,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum BtcNetwork {
+    #[default]
+    Testnet,
+    Signet,
+    Regtest,
+    Mainnet,
+}
+
+impl BtcNetwork {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BtcNetwork::Testnet => "testnet",
+            BtcNetwork::Signet => "signet",
+            BtcNetwork::Regtest => "regtest",
+            BtcNetwork::Mainnet => "mainnet",
+        }
+    }
+
+    pub fn bitcoin(self) -> bitcoin::Network {
+        match self {
+            BtcNetwork::Testnet => bitcoin::Network::Testnet,
+            BtcNetwork::Signet => bitcoin::Network::Signet,
+            BtcNetwork::Regtest => bitcoin::Network::Regtest,
+            BtcNetwork::Mainnet => bitcoin::Network::Bitcoin,
+        }
+    }
+
+    /// SLIP-44 coin type: 0 for mainnet, 1 for every test network.
+    pub fn coin_type(self) -> u32 {
+        match self {
+            BtcNetwork::Mainnet => 0,
+            _ => 1,
+        }
+    }
+
+    /// The flag that selects this network on the borrower CLI; mainnet is the
+    /// CLI's default and takes none.
+    pub fn cli_flag(self) -> Option<&'static str> {
+        match self {
+            BtcNetwork::Testnet => Some("--testnet"),
+            BtcNetwork::Signet => Some("--signet"),
+            BtcNetwork::Regtest => Some("--regtest"),
+            BtcNetwork::Mainnet => None,
+        }
+    }
+}
+
+impl fmt::Display for BtcNetwork {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for BtcNetwork {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "testnet" => Ok(BtcNetwork::Testnet),
+            "signet" => Ok(BtcNetwork::Signet),
+            "regtest" => Ok(BtcNetwork::Regtest),
+            "mainnet" => Ok(BtcNetwork::Mainnet),
+            _ => Err(format!("Unknown network '{}'", s)),
+        }
+    }
+}
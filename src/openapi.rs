@@ -0,0 +1,187 @@
+use crate::address::AddressScheme;
+use crate::artifacts::LogChunk;
+use crate::db::{AuditEntry, CliVersionSummary, PooledWallet, TestResult};
+use crate::diff::{FieldChange, RunDiff, RunSide, StepDiff, StepOutcome, StreamDiff};
+use crate::events::{LogEvent, LogLevel};
+use crate::faucet::{Confirmation, FaucetAsset, FaucetReceipt};
+use crate::load::{
+    BaselineComparison, HistogramBucket, LoadReport, LoadRequest, LoadRun, LoadSuite,
+    StepComparison, StepLatency,
+};
+use crate::matrix::{CellStatus, MatrixCell, MatrixRequest, MatrixRun, MatrixSummary};
+use crate::negative::{ExpectedFailure, NegativeCommand, NegativeScenario};
+use crate::network::BtcNetwork;
+use crate::scenario::{
+    Assertion, Capture, ScenarioCheckpoint, ScenarioDefinition, ScenarioFile, ScenarioStep,
+};
+use crate::test_suite::{ErrorKind, TestError};
+use crate::{NewWallet, TestOptions};
+use actix_web::http::header;
+use actix_web::{web, HttpResponse, Responder};
+use std::sync::Arc;
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::Config;
+
+/// The API description, generated from the handler annotations and the
+/// request/response types so it can't drift from the code.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Borrower CLI Test Server",
+        description = "Runs end-to-end tests of the Lava borrower CLI and stores their results."
+    ),
+    paths(
+        crate::health_check,
+        crate::run_test,
+        crate::get_scenarios,
+        crate::get_scenario_files,
+        crate::get_scenario_runs,
+        crate::create_matrix_run,
+        crate::get_matrix_runs,
+        crate::get_matrix_run,
+        crate::create_load_run,
+        crate::get_load_runs,
+        crate::get_load_run,
+        crate::set_load_baseline,
+        crate::get_all_results,
+        crate::get_cli_version_summary,
+        crate::get_result,
+        crate::get_result_events,
+        crate::get_result_logs,
+        crate::get_result_diff,
+        crate::get_last_successful_mnemonic,
+        crate::get_wallets,
+        crate::add_wallet,
+        crate::delete_wallet,
+        crate::get_config,
+        crate::get_audit_log,
+        crate::get_metrics,
+    ),
+    components(schemas(
+        TestOptions,
+        NegativeScenario,
+        NegativeCommand,
+        ExpectedFailure,
+        ScenarioFile,
+        ScenarioDefinition,
+        ScenarioStep,
+        Capture,
+        Assertion,
+        ScenarioCheckpoint,
+        MatrixRequest,
+        MatrixRun,
+        MatrixCell,
+        MatrixSummary,
+        LoadRequest,
+        LoadRun,
+        LoadSuite,
+        LoadReport,
+        StepLatency,
+        HistogramBucket,
+        BaselineComparison,
+        StepComparison,
+        CellStatus,
+        BtcNetwork,
+        AddressScheme,
+        TestResult,
+        TestError,
+        ErrorKind,
+        FaucetAsset,
+        FaucetReceipt,
+        Confirmation,
+        LogEvent,
+        RunDiff,
+        RunSide,
+        FieldChange,
+        StepDiff,
+        StepOutcome,
+        StreamDiff,
+        LogLevel,
+        LogChunk,
+        CliVersionSummary,
+        AuditEntry,
+        PooledWallet,
+        NewWallet,
+    )),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    tags(
+        (name = "runs", description = "Starting test runs"),
+        (name = "results", description = "Stored results and their logs"),
+        (name = "wallets", description = "The pool of funded wallets reused across runs"),
+        (name = "server", description = "Health, configuration and operations"),
+    )
+)]
+pub struct ApiDoc;
+
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+    }
+}
+
+pub async fn openapi_json() -> impl Responder {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+/// Swagger UI's page and assets, built into the binary and pointed at
+/// `/openapi.json`.
+pub async fn swagger_ui(path: web::Path<String>) -> impl Responder {
+    let config = Arc::new(Config::from("/openapi.json"));
+    match utoipa_swagger_ui::serve(&path, config) {
+        Ok(Some(file)) => HttpResponse::Ok()
+            .content_type(file.content_type)
+            .body(file.bytes.into_owned()),
+        Ok(None) => HttpResponse::NotFound().finish(),
+        Err(e) => HttpResponse::InternalServerError().body(e.to_string()),
+    }
+}
+
+/// The page loads its assets relative to `/docs/`.
+pub async fn swagger_ui_redirect() -> impl Responder {
+    HttpResponse::Found()
+        .insert_header((header::LOCATION, "/docs/"))
+        .finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{test, App};
+
+    #[actix_web::test]
+    async fn swagger_ui_is_served_from_the_binary() {
+        let app = test::init_service(
+            App::new()
+                .route("/docs", web::get().to(swagger_ui_redirect))
+                .route("/docs/{path:.*}", web::get().to(swagger_ui)),
+        )
+        .await;
+
+        let response = test::call_service(&app, test::TestRequest::get().uri("/docs").to_request()).await;
+        assert_eq!(response.headers().get(header::LOCATION).unwrap(), "/docs/");
+
+        let page = test::call_and_read_body(&app, test::TestRequest::get().uri("/docs/").to_request()).await;
+        let page = String::from_utf8_lossy(&page);
+        assert!(page.contains("swagger-ui"));
+        assert!(!page.contains("unpkg.com"));
+
+        let initializer = test::TestRequest::get().uri("/docs/swagger-initializer.js").to_request();
+        let initializer = test::call_and_read_body(&app, initializer).await;
+        assert!(String::from_utf8_lossy(&initializer).contains("/openapi.json"));
+
+        let css = test::call_service(&app, test::TestRequest::get().uri("/docs/swagger-ui.css").to_request()).await;
+        assert!(css.status().is_success());
+        assert_eq!(css.headers().get(header::CONTENT_TYPE).unwrap(), "text/css");
+
+        let missing = test::call_service(&app, test::TestRequest::get().uri("/docs/nope.js").to_request()).await;
+        assert_eq!(missing.status(), actix_web::http::StatusCode::NOT_FOUND);
+    }
+}
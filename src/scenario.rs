@@ -0,0 +1,550 @@
+use crate::address::AddressScheme;
+use crate::config::SuiteConfig;
+use crate::faucet::FaucetReceipt;
+use crate::negative::{self, ExpectedFailure, NegativeScenario};
+use crate::network::BtcNetwork;
+use crate::test_suite::TestSuite;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
+
+/// File extensions scenario files are loaded from.
+const EXTENSIONS: &[&str] = &["yaml", "yml", "json"];
+
+/// A suite run described as data: the steps to take, what to capture from
+/// the CLI's output and what the contract JSON must look like.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ScenarioDefinition {
+    #[serde(default)]
+    pub description: String,
+    /// Checkpoint the run after every step so the server picks it up again
+    /// after a restart. Resumable runs start in the background.
+    #[serde(default)]
+    pub resumable: bool,
+    pub steps: Vec<ScenarioStep>,
+}
+
+/// One step of a scenario. String arguments may reference captured values as
+/// `{name}`; `btc_address` and `lava_pubkey` are set by `generate_wallet`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(tag = "step", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ScenarioStep {
+    /// Generates a wallet, or uses the run's mnemonic
+    GenerateWallet,
+    /// Calls the network's faucets and waits for the funds like the standard
+    /// flow does
+    Fund,
+    /// `borrow init`; loan parameters default to `suite.loan`
+    BorrowInit {
+        #[serde(default)]
+        ltv_ratio_bp: Option<u32>,
+        #[serde(default)]
+        duration_days: Option<u32>,
+        #[serde(default)]
+        amount: Option<u64>,
+        #[serde(default)]
+        capital_asset: Option<String>,
+        /// Extra arguments appended to the command
+        #[serde(default)]
+        args: Vec<String>,
+        /// Values read from the output; defaults to the contract id
+        #[serde(default = "default_captures")]
+        captures: Vec<Capture>,
+        /// Also store the new contract's id under this name, to tell apart
+        /// several loans open at once
+        #[serde(default)]
+        save_as: Option<String>,
+        /// The command must fail this way instead of succeeding
+        #[serde(default)]
+        expect_failure: Option<ExpectedFailure>,
+    },
+    /// `borrow repay`
+    Repay {
+        #[serde(default = "default_contract_id")]
+        contract_id: String,
+        #[serde(default)]
+        args: Vec<String>,
+        #[serde(default)]
+        captures: Vec<Capture>,
+        #[serde(default)]
+        expect_failure: Option<ExpectedFailure>,
+    },
+    /// `get-contract`, checking the contract JSON it writes
+    GetContract {
+        #[serde(default = "default_contract_id")]
+        contract_id: String,
+        #[serde(default)]
+        assert: Vec<Assertion>,
+        /// Values stored from the contract JSON by name, after the
+        /// assertions ran, e.g. to compare a balance with the next one
+        #[serde(default)]
+        capture: BTreeMap<String, String>,
+    },
+    /// Polls `get-contract` until every assertion holds
+    WaitForState {
+        #[serde(default = "default_contract_id")]
+        contract_id: String,
+        until: Vec<Assertion>,
+        timeout_secs: u64,
+        #[serde(default = "default_poll_secs")]
+        poll_secs: u64,
+    },
+    /// Pauses the run, e.g. to let the backend settle between commands
+    Sleep { secs: u64 },
+}
+
+impl ScenarioStep {
+    /// How the step is listed in `steps_completed`.
+    pub fn completed(&self) -> &'static str {
+        match self {
+            ScenarioStep::GenerateWallet => "Generated wallet",
+            ScenarioStep::Fund => "Funded wallet",
+            ScenarioStep::BorrowInit { .. } => "Ran borrow init",
+            ScenarioStep::Repay { .. } => "Ran borrow repay",
+            ScenarioStep::GetContract { .. } => "Checked contract",
+            ScenarioStep::WaitForState { .. } => "Contract reached the expected state",
+            ScenarioStep::Sleep { .. } => "Slept",
+        }
+    }
+
+    pub fn runs_cli(&self) -> bool {
+        !matches!(
+            self,
+            ScenarioStep::GenerateWallet | ScenarioStep::Fund | ScenarioStep::Sleep { .. }
+        )
+    }
+}
+
+fn default_captures() -> Vec<Capture> {
+    vec![Capture {
+        name: "contract_id".to_string(),
+        pattern: crate::test_suite::CONTRACT_ID_PATTERN.to_string(),
+        optional: false,
+    }]
+}
+
+fn default_contract_id() -> String {
+    "{contract_id}".to_string()
+}
+
+fn default_poll_secs() -> u64 {
+    30
+}
+
+/// Stores the first group of `pattern`, searched in stdout and then stderr,
+/// as `{name}`. Capturing `contract_id` also sets the result's contract id.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Capture {
+    pub name: String,
+    pub pattern: String,
+    /// Don't fail the step when the pattern doesn't match
+    #[serde(default)]
+    pub optional: bool,
+}
+
+impl Capture {
+    pub fn find(&self, stdout: &str, stderr: &str) -> Option<String> {
+        let re = Regex::new(&self.pattern).ok()?;
+        re.captures(stdout)
+            .or_else(|| re.captures(stderr))
+            .and_then(|captures| captures.get(1))
+            .map(|m| m.as_str().to_string())
+    }
+}
+
+/// A check on the value at a JSON pointer (RFC 6901) into the contract JSON.
+/// With no condition set the value only has to exist. `pointer`, `matches`
+/// and string bounds may refer to captured values as `{name}`.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct Assertion {
+    pub pointer: String,
+    #[serde(default)]
+    pub exists: Option<bool>,
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub equals: Option<Value>,
+    /// Regex the value (as a string, or its JSON text) must match
+    #[serde(default)]
+    pub matches: Option<String>,
+    /// The value, read as a number, must be below this
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub less_than: Option<Value>,
+    /// The value, read as a number, must be above this
+    #[serde(default)]
+    #[schema(value_type = Option<Object>)]
+    pub greater_than: Option<Value>,
+}
+
+impl Assertion {
+    pub fn check(&self, json: &Value, values: &HashMap<String, String>) -> Result<(), String> {
+        let pointer = substitute(&self.pointer, values)?;
+        let value = json.pointer(&pointer);
+        if self.exists == Some(false) {
+            return match value {
+                Some(value) => Err(format!("{} should not exist but is {}", pointer, value)),
+                None => Ok(()),
+            };
+        }
+        let value = value.ok_or_else(|| format!("{} does not exist", pointer))?;
+        if let Some(expected) = &self.equals {
+            if value != expected {
+                return Err(format!("{} is {}, expected {}", pointer, value, expected));
+            }
+        }
+        if let Some(pattern) = &self.matches {
+            let pattern = substitute(pattern, values)?;
+            let re = Regex::new(&pattern)
+                .map_err(|e| format!("Invalid pattern '{}': {}", pattern, e))?;
+            if !re.is_match(&value_text(value)) {
+                return Err(format!("{} is {}, which does not match /{}/", pointer, value, pattern));
+            }
+        }
+        for (bound, below) in [(&self.less_than, true), (&self.greater_than, false)] {
+            let Some(bound) = bound else { continue };
+            let limit = match bound {
+                Value::String(template) => substitute(template, values)?,
+                other => other.to_string(),
+            };
+            let limit: f64 = limit
+                .parse()
+                .map_err(|_| format!("{} is not a number to compare {} with", limit, pointer))?;
+            let actual = value_text(value)
+                .parse::<f64>()
+                .map_err(|_| format!("{} is {}, which is not a number", pointer, value))?;
+            if below && actual >= limit {
+                return Err(format!("{} is {}, expected less than {}", pointer, value, limit));
+            }
+            if !below && actual <= limit {
+                return Err(format!("{} is {}, expected more than {}", pointer, value, limit));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A JSON value as text: strings without their quotes, anything else as JSON.
+pub fn value_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
+
+/// Progress of a resumable scenario run, saved after every step. The server
+/// resumes unfinished runs from `next_step` when it starts.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct ScenarioCheckpoint {
+    /// Id the run's result will be saved under
+    pub run_id: String,
+    pub scenario: String,
+    pub path: String,
+    /// The scenario file's text when the run started
+    pub source: String,
+    pub network: BtcNetwork,
+    pub mnemonic: String,
+    pub account_index: u32,
+    pub address_scheme: AddressScheme,
+    pub derivation_path: Option<String>,
+    pub btc_address: String,
+    /// Index into the scenario's steps of the first one still to run
+    pub next_step: usize,
+    /// Captured values
+    pub values: HashMap<String, String>,
+    pub steps_completed: Vec<String>,
+    pub contract_id: Option<String>,
+    #[serde(default)]
+    pub contract_ids: Vec<String>,
+    pub faucet_sats: Option<u64>,
+    pub btc_balance_sats: Option<u64>,
+    pub faucet_receipts: Vec<FaucetReceipt>,
+    /// When the `wait_for_state` step at `next_step` gives up
+    pub wait_deadline: Option<DateTime<Utc>>,
+    pub started_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub triggered_by: Option<String>,
+}
+
+impl ScenarioCheckpoint {
+    pub fn redact_secrets(&mut self) {
+        self.mnemonic = "***".to_string();
+    }
+
+    /// The scenario file the run executes, as it was when the run started.
+    pub fn scenario_file(&self) -> Result<ScenarioFile, String> {
+        let definition = ScenarioDefinition::parse(Path::new(&self.path), &self.source)?;
+        Ok(ScenarioFile {
+            name: self.scenario.clone(),
+            path: self.path.clone(),
+            definition,
+            source: self.source.clone(),
+        })
+    }
+}
+
+/// Receives a resumable run's checkpoints, e.g. to store them.
+pub type CheckpointSink = Box<dyn FnMut(&ScenarioCheckpoint) + Send>;
+
+/// A scenario loaded from `suite.scenarios_dir`, named after its file.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ScenarioFile {
+    pub name: String,
+    pub path: String,
+    pub definition: ScenarioDefinition,
+    /// The file as it was read, stored with results for reproducibility
+    pub source: String,
+}
+
+impl ScenarioDefinition {
+    /// Parses YAML, or JSON when `path` ends in `.json`.
+    pub fn parse(path: &Path, source: &str) -> Result<Self, String> {
+        let definition: ScenarioDefinition = if path.extension().is_some_and(|ext| ext == "json") {
+            serde_json::from_str(source).map_err(|e| e.to_string())?
+        } else {
+            serde_yaml::from_str(source).map_err(|e| e.to_string())?
+        };
+        definition.validate()?;
+        Ok(definition)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.steps.is_empty() {
+            return Err("a scenario needs at least one step".to_string());
+        }
+        let mut wallet = false;
+        for (index, step) in self.steps.iter().enumerate() {
+            let at = |problem: String| format!("step {}: {}", index + 1, problem);
+            if let ScenarioStep::BorrowInit { save_as: Some(name), .. } = step {
+                if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                    return Err(at(format!("save_as '{}' may only use letters, digits and '_'", name)));
+                }
+            }
+            match step {
+                ScenarioStep::GenerateWallet => wallet = true,
+                step if !wallet && (step.runs_cli() || matches!(step, ScenarioStep::Fund)) => {
+                    return Err(at("generate_wallet must come first".to_string()));
+                }
+                _ => {}
+            }
+            let (captures, expect_failure, assertions): (&[Capture], _, &[Assertion]) = match step {
+                ScenarioStep::BorrowInit { captures, expect_failure, .. }
+                | ScenarioStep::Repay { captures, expect_failure, .. } => {
+                    (captures, expect_failure.as_ref(), &[])
+                }
+                ScenarioStep::GetContract { assert, capture, .. } => {
+                    if let Some((name, pointer)) =
+                        capture.iter().find(|(_, pointer)| !pointer.starts_with('/'))
+                    {
+                        return Err(at(format!("capture '{}' pointer '{}' must start with '/'", name, pointer)));
+                    }
+                    (&[], None, assert)
+                }
+                ScenarioStep::WaitForState { until, timeout_secs, poll_secs, .. } => {
+                    if until.is_empty() {
+                        return Err(at("wait_for_state needs at least one `until` assertion".to_string()));
+                    }
+                    if *timeout_secs == 0 || *poll_secs == 0 {
+                        return Err(at("timeout_secs and poll_secs must be at least 1".to_string()));
+                    }
+                    (&[], None, until)
+                }
+                _ => (&[], None, &[]),
+            };
+            for capture in captures {
+                match Regex::new(&capture.pattern) {
+                    Ok(re) if re.captures_len() >= 2 => {}
+                    Ok(_) => return Err(at(format!("capture '{}' needs a capture group", capture.name))),
+                    Err(e) => return Err(at(format!("capture '{}' is not a valid regex: {}", capture.name, e))),
+                }
+            }
+            if let Some(pattern) = expect_failure.and_then(|expect| expect.stderr_pattern.as_ref()) {
+                Regex::new(pattern)
+                    .map_err(|e| at(format!("stderr_pattern is not a valid regex: {}", e)))?;
+            }
+            for assertion in assertions {
+                if !assertion.pointer.is_empty() && !assertion.pointer.starts_with('/') {
+                    return Err(at(format!("pointer '{}' must start with '/'", assertion.pointer)));
+                }
+                for bound in [&assertion.less_than, &assertion.greater_than].into_iter().flatten() {
+                    if !(bound.is_number() || bound.is_string()) {
+                        return Err(at(format!("bound {} on {} must be a number or a string", bound, assertion.pointer)));
+                    }
+                }
+                if let Some(pattern) = assertion.matches.as_ref().filter(|pattern| !pattern.contains('{')) {
+                    Regex::new(pattern).map_err(|e| at(format!("matches is not a valid regex: {}", e)))?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Replaces each `{name}` in `template` with its captured value.
+pub fn substitute(template: &str, values: &HashMap<String, String>) -> Result<String, String> {
+    let re = Regex::new(r"\{([A-Za-z0-9_]+)\}").unwrap();
+    let mut missing = None;
+    let result = re.replace_all(template, |captures: &regex::Captures| {
+        match values.get(&captures[1]) {
+            Some(value) => value.clone(),
+            None => {
+                missing.get_or_insert_with(|| captures[1].to_string());
+                String::new()
+            }
+        }
+    });
+    match missing {
+        Some(name) => Err(format!("'{}' refers to {{{}}}, which has not been captured", template, name)),
+        None => Ok(result.into_owned()),
+    }
+}
+
+fn scenario_paths(dir: &str) -> Vec<PathBuf> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.is_file()
+                    && path
+                        .extension()
+                        .and_then(|ext| ext.to_str())
+                        .is_some_and(|ext| EXTENSIONS.contains(&ext))
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+    paths
+}
+
+/// Reads and validates the scenario file at `path`.
+pub fn load(path: &Path) -> Result<ScenarioFile, String> {
+    let source = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let definition =
+        ScenarioDefinition::parse(path, &source).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(ScenarioFile {
+        name,
+        path: path.display().to_string(),
+        definition,
+        source,
+    })
+}
+
+/// Every scenario in `dir`, and a message for each file that failed to load.
+/// A missing directory holds no scenarios.
+pub fn load_all(dir: &str) -> (Vec<ScenarioFile>, Vec<String>) {
+    let mut scenarios = Vec::new();
+    let mut problems = Vec::new();
+    for path in scenario_paths(dir) {
+        match load(&path) {
+            Ok(scenario) => scenarios.push(scenario),
+            Err(e) => problems.push(e),
+        }
+    }
+    (scenarios, problems)
+}
+
+/// The scenario file named `name` in `dir`. Files are read on every lookup,
+/// so edits take effect without a restart.
+pub fn find(dir: &str, name: &str) -> Result<Option<ScenarioFile>, String> {
+    scenario_paths(dir)
+        .into_iter()
+        .find(|path| path.file_stem().is_some_and(|stem| stem == name))
+        .map(|path| load(&path))
+        .transpose()
+}
+
+/// What a run's `scenario` option names. A scenario file takes precedence
+/// over a negative scenario of the same name.
+#[derive(Clone)]
+pub enum NamedScenario {
+    File(ScenarioFile),
+    Negative(NegativeScenario),
+}
+
+impl NamedScenario {
+    pub fn find(config: &SuiteConfig, name: &str) -> Result<Self, String> {
+        if let Some(file) = find(&config.scenarios_dir, name)? {
+            return Ok(NamedScenario::File(file));
+        }
+        negative::find(config, name)
+            .map(NamedScenario::Negative)
+            .ok_or_else(|| format!("Unknown scenario '{}'", name))
+    }
+
+    pub fn apply(self, suite: TestSuite) -> TestSuite {
+        match self {
+            NamedScenario::File(file) => suite.with_scenario_file(file),
+            NamedScenario::Negative(scenario) => suite.with_negative_scenario(scenario),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn values() -> HashMap<String, String> {
+        HashMap::from([
+            ("contract_id".to_string(), "C42".to_string()),
+            ("outstanding".to_string(), "150".to_string()),
+        ])
+    }
+
+    fn assertion(pointer: &str) -> Assertion {
+        Assertion {
+            pointer: pointer.to_string(),
+            exists: None,
+            equals: None,
+            matches: None,
+            less_than: None,
+            greater_than: None,
+        }
+    }
+
+    #[test]
+    fn substitute_fills_in_captured_values() {
+        assert_eq!(substitute("/{contract_id}/state", &values()).unwrap(), "/C42/state");
+        assert_eq!(substitute("no placeholders", &values()).unwrap(), "no placeholders");
+        let error = substitute("/{missing}", &values()).unwrap_err();
+        assert!(error.contains("{missing}"), "{}", error);
+    }
+
+    #[test]
+    fn assertions_check_existence_and_equality() {
+        let contract = json!({ "C42": { "state": "Open", "balance": 120 } });
+        assert!(assertion("/{contract_id}/state").check(&contract, &values()).is_ok());
+        assert!(assertion("/C42/closed_at").check(&contract, &values()).is_err());
+        let absent = Assertion { exists: Some(false), ..assertion("/C42/closed_at") };
+        assert!(absent.check(&contract, &values()).is_ok());
+        let equals = Assertion { equals: Some(json!("Closed")), ..assertion("/C42/state") };
+        assert!(equals.check(&contract, &values()).is_err());
+    }
+
+    #[test]
+    fn assertions_compare_patterns_and_bounds() {
+        let contract = json!({ "state": "Open", "balance": "120" });
+        let matches = Assertion { matches: Some("^Op".to_string()), ..assertion("/state") };
+        assert!(matches.check(&contract, &values()).is_ok());
+        let below = Assertion { less_than: Some(json!("{outstanding}")), ..assertion("/balance") };
+        assert!(below.check(&contract, &values()).is_ok());
+        let above = Assertion { greater_than: Some(json!(120)), ..assertion("/balance") };
+        assert!(above.check(&contract, &values()).is_err());
+        let not_a_number = Assertion { less_than: Some(json!(1)), ..assertion("/state") };
+        assert!(not_a_number.check(&contract, &values()).is_err());
+    }
+}
@@ -0,0 +1,90 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+
+const NONCE_LEN: usize = 12;
+
+/// Encrypts secrets that are stored alongside results, such as BIP39
+/// passphrases, with AES-256-GCM under `server.secret_key`.
+pub struct SecretBox {
+    cipher: Aes256Gcm,
+}
+
+impl SecretBox {
+    /// `key_hex` is a 32-byte key as 64 hex characters.
+    pub fn new(key_hex: &str) -> Result<Self, String> {
+        let key = hex::decode(key_hex).map_err(|e| format!("Secret key is not hex: {}", e))?;
+        if key.len() != 32 {
+            return Err(format!("Secret key must be 32 bytes, got {}", key.len()));
+        }
+        Ok(SecretBox {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key)),
+        })
+    }
+
+    /// Returns the hex-encoded nonce followed by the ciphertext.
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext.as_bytes())
+            .expect("AES-GCM encryption does not fail for in-memory buffers");
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        hex::encode(sealed)
+    }
+
+    pub fn decrypt(&self, sealed_hex: &str) -> Result<String, String> {
+        let sealed = hex::decode(sealed_hex).map_err(|e| format!("Invalid ciphertext: {}", e))?;
+        if sealed.len() < NONCE_LEN {
+            return Err("Ciphertext is too short".to_string());
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| "Decryption failed; was the secret key changed?".to_string())?;
+        String::from_utf8(plaintext).map_err(|e| format!("Decrypted secret is not UTF-8: {}", e))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: &str = "000102030405060708090a0b0c0d0e0f101112131415161718191a1b1c1d1e1f";
+
+    #[test]
+    fn round_trips_with_a_fresh_nonce() {
+        let secrets = SecretBox::new(KEY).unwrap();
+        let first = secrets.encrypt("correct horse");
+        let second = secrets.encrypt("correct horse");
+        assert_ne!(first, second);
+        assert!(!first.contains(&hex::encode("correct horse")));
+        assert_eq!(secrets.decrypt(&first).unwrap(), "correct horse");
+        assert_eq!(secrets.decrypt(&second).unwrap(), "correct horse");
+    }
+
+    #[test]
+    fn rejects_tampered_ciphertext() {
+        let secrets = SecretBox::new(KEY).unwrap();
+        let mut sealed = hex::decode(secrets.encrypt("correct horse")).unwrap();
+        let last = sealed.len() - 1;
+        sealed[last] ^= 1;
+        assert!(secrets.decrypt(&hex::encode(&sealed)).is_err());
+        assert!(secrets.decrypt("00").is_err());
+        assert!(secrets.decrypt("not hex").is_err());
+    }
+
+    #[test]
+    fn rejects_another_key() {
+        let sealed = SecretBox::new(KEY).unwrap().encrypt("correct horse");
+        let other = SecretBox::new(&"11".repeat(32)).unwrap();
+        assert!(other.decrypt(&sealed).is_err());
+    }
+
+    #[test]
+    fn keys_must_be_32_hex_bytes() {
+        assert!(SecretBox::new(&"00".repeat(16)).is_err());
+        assert!(SecretBox::new("zz").is_err());
+    }
+}
@@ -0,0 +1,301 @@
+use crate::address::{self, AddressScheme};
+use crate::config::{PoolConfig, SuiteConfig};
+use crate::db::{Database, PooledWallet, TestResult};
+use crate::esplora::EsploraClient;
+use crate::network::BtcNetwork;
+use crate::test_suite::{TestError, TestSuite};
+use chrono::{Duration, Utc};
+use std::fmt;
+use uuid::Uuid;
+
+/// Every pooled wallet on the network is leased and the pool is full.
+#[derive(Debug)]
+pub struct PoolExhausted {
+    pub network: BtcNetwork,
+    pub max_wallets: u32,
+}
+
+impl fmt::Display for PoolExhausted {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "All {} pooled {} wallets are in use",
+            self.max_wallets, self.network
+        )
+    }
+}
+
+/// Leases a wallet for a run on `network`. `Ok(None)` means the pool has
+/// room, so the run should generate a wallet that joins it afterwards.
+pub fn lease(
+    db: &Database,
+    config: &PoolConfig,
+    network: BtcNetwork,
+) -> rusqlite::Result<Result<Option<PooledWallet>, PoolExhausted>> {
+    let stale_before = Utc::now() - Duration::seconds(config.lease_timeout_secs as i64);
+    if let Some(wallet) = db.lease_wallet(network, stale_before)? {
+        return Ok(Ok(Some(wallet)));
+    }
+    if db.count_wallets(network)? < config.max_wallets {
+        return Ok(Ok(None));
+    }
+    Ok(Err(PoolExhausted {
+        network,
+        max_wallets: config.max_wallets,
+    }))
+}
+
+/// Points `suite` at a leased wallet, skipping the faucet while its last
+/// known balance is at or above `low_balance_sats`.
+pub fn apply(suite: TestSuite, config: &PoolConfig, wallet: &PooledWallet) -> TestSuite {
+    let suite = suite.with_mnemonic(wallet.mnemonic.clone());
+    match wallet.balance_sats {
+        Some(balance) if balance >= config.low_balance_sats => suite.without_faucet(format!(
+            "pooled wallet {} holds {} sats (top-up below {})",
+            wallet.id, balance, config.low_balance_sats
+        )),
+        _ => suite,
+    }
+}
+
+/// Looks up what `address` holds after a run, through `suite.esplora_urls`.
+/// `None` when the network has no Esplora URL or the lookup fails.
+pub async fn current_balance(
+    config: &SuiteConfig,
+    network: BtcNetwork,
+    address: &str,
+) -> Option<u64> {
+    let url = config.esplora_urls.get(&network)?;
+    match EsploraClient::new(url).address_balance(address).await {
+        Ok(balance) => Some(balance.total_sats()),
+        Err(e) => {
+            log::warn!("Failed to refresh the balance of {}: {}", address, e);
+            None
+        }
+    }
+}
+
+/// Returns a leased wallet after the run with `balance_sats`, its balance
+/// after the run if known, or adds a wallet a successful run generated and
+/// funded to the pool. Wallets of failed runs may still have collateral
+/// locked in an open contract, so they are left out.
+pub fn settle(
+    db: &Database,
+    config: &PoolConfig,
+    wallet: Option<&PooledWallet>,
+    balance_sats: Option<u64>,
+    result: &mut TestResult,
+) -> rusqlite::Result<()> {
+    match wallet {
+        Some(wallet) => {
+            result.wallet_id = Some(wallet.id.clone());
+            db.release_wallet(&wallet.id, balance_sats)
+        }
+        None => {
+            if !result.success {
+                return Ok(());
+            }
+            let network = match (result.network, result.faucet_sats) {
+                (Some(network), Some(_)) => network,
+                _ => return Ok(()),
+            };
+            if db.count_wallets(network)? >= config.max_wallets {
+                return Ok(());
+            }
+            let now = Utc::now().to_rfc3339();
+            let wallet = PooledWallet {
+                id: Uuid::new_v4().to_string(),
+                network,
+                mnemonic: result.mnemonic.clone(),
+                btc_address: result.btc_address.clone(),
+                balance_sats,
+                balance_updated_at: balance_sats.map(|_| now.clone()),
+                leased_at: None,
+                last_used_at: Some(now.clone()),
+                runs: 1,
+                created_at: now,
+            };
+            db.add_wallet(&wallet)?;
+            log::info!("Added wallet {} to the {} pool", wallet.id, network);
+            result.wallet_id = Some(wallet.id);
+            Ok(())
+        }
+    }
+}
+
+/// Builds a pool entry for a wallet funded by hand.
+/// The address is derived the way pooled runs derive it, with the suite's
+/// default scheme and account.
+pub fn import(
+    mnemonic: &str,
+    network: BtcNetwork,
+    scheme: AddressScheme,
+    account: u32,
+    balance_sats: Option<u64>,
+) -> Result<PooledWallet, TestError> {
+    let btc_address = address::derive_address(
+        mnemonic,
+        "",
+        network,
+        scheme,
+        &scheme.default_path(network, account),
+    )?;
+    let now = Utc::now().to_rfc3339();
+    Ok(PooledWallet {
+        id: Uuid::new_v4().to_string(),
+        network,
+        mnemonic: mnemonic.to_string(),
+        btc_address,
+        balance_sats,
+        balance_updated_at: balance_sats.map(|_| now.clone()),
+        leased_at: None,
+        last_used_at: None,
+        runs: 0,
+        created_at: now,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifacts::ArtifactStore;
+    use std::net::TcpListener;
+
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn database() -> Database {
+        let dir = std::env::temp_dir().join(format!("pool-test-{}", Uuid::new_v4()));
+        Database::new(":memory:", ArtifactStore::new(&dir.to_string_lossy(), 1024 * 1024), None).unwrap()
+    }
+
+    fn pool(max_wallets: u32) -> PoolConfig {
+        PoolConfig {
+            enabled: true,
+            max_wallets,
+            ..PoolConfig::default()
+        }
+    }
+
+    fn pooled(balance_sats: Option<u64>) -> PooledWallet {
+        import(MNEMONIC, BtcNetwork::Testnet, AddressScheme::default(), 0, balance_sats).unwrap()
+    }
+
+    fn funded_result(success: bool) -> TestResult {
+        TestResult {
+            success,
+            mnemonic: MNEMONIC.to_string(),
+            btc_address: "tb1qexample".to_string(),
+            network: Some(BtcNetwork::Testnet),
+            faucet_sats: Some(100000),
+            ..TestResult::empty("run")
+        }
+    }
+
+    #[test]
+    fn a_leased_wallet_is_not_leased_again() {
+        let db = database();
+        let config = pool(1);
+        db.add_wallet(&pooled(Some(100000))).unwrap();
+
+        let wallet = lease(&db, &config, BtcNetwork::Testnet).unwrap().unwrap().unwrap();
+        assert!(wallet.leased_at.is_some());
+        let exhausted = lease(&db, &config, BtcNetwork::Testnet).unwrap().unwrap_err();
+        assert_eq!(exhausted.max_wallets, 1);
+        // Other networks have pools of their own
+        assert!(lease(&db, &config, BtcNetwork::Signet).unwrap().unwrap().is_none());
+
+        settle(&db, &config, Some(&wallet), Some(90000), &mut funded_result(true)).unwrap();
+        let again = lease(&db, &config, BtcNetwork::Testnet).unwrap().unwrap().unwrap();
+        assert_eq!(again.id, wallet.id);
+        assert_eq!(again.balance_sats, Some(90000));
+        assert_eq!(again.runs, 1);
+    }
+
+    #[test]
+    fn a_pool_with_room_generates_a_wallet() {
+        let db = database();
+        db.add_wallet(&pooled(None)).unwrap();
+        let config = pool(2);
+
+        assert!(lease(&db, &config, BtcNetwork::Testnet).unwrap().unwrap().is_some());
+        // The only wallet is out, but the pool has room for another
+        assert!(lease(&db, &config, BtcNetwork::Testnet).unwrap().unwrap().is_none());
+    }
+
+    #[test]
+    fn abandoned_leases_are_reclaimed() {
+        let db = database();
+        db.add_wallet(&pooled(None)).unwrap();
+        let config = PoolConfig {
+            lease_timeout_secs: 0,
+            ..pool(1)
+        };
+
+        let first = lease(&db, &config, BtcNetwork::Testnet).unwrap().unwrap().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        let second = lease(&db, &config, BtcNetwork::Testnet).unwrap().unwrap().unwrap();
+        assert_eq!(first.id, second.id);
+    }
+
+    #[test]
+    fn settle_pools_only_successful_funded_runs() {
+        let db = database();
+        let config = pool(1);
+
+        let mut failed = funded_result(false);
+        settle(&db, &config, None, Some(100000), &mut failed).unwrap();
+        assert_eq!(failed.wallet_id, None);
+
+        let mut unfunded = TestResult {
+            faucet_sats: None,
+            ..funded_result(true)
+        };
+        settle(&db, &config, None, Some(100000), &mut unfunded).unwrap();
+        assert_eq!(unfunded.wallet_id, None);
+        assert_eq!(db.count_wallets(BtcNetwork::Testnet).unwrap(), 0);
+
+        let mut passed = funded_result(true);
+        settle(&db, &config, None, Some(100000), &mut passed).unwrap();
+        let wallets = db.get_wallets().unwrap();
+        assert_eq!(wallets.len(), 1);
+        assert_eq!(passed.wallet_id.as_ref(), Some(&wallets[0].id));
+        assert_eq!(wallets[0].mnemonic, MNEMONIC);
+        assert_eq!(wallets[0].balance_sats, Some(100000));
+
+        // The pool is full now
+        let mut another = funded_result(true);
+        settle(&db, &config, None, None, &mut another).unwrap();
+        assert_eq!(another.wallet_id, None);
+        assert_eq!(db.count_wallets(BtcNetwork::Testnet).unwrap(), 1);
+    }
+
+    #[test]
+    fn apply_skips_the_faucet_at_or_above_the_low_balance() {
+        let config = pool(1);
+        let suite = || TestSuite::new(SuiteConfig::default());
+
+        for balance in [config.low_balance_sats, config.low_balance_sats + 1] {
+            let applied = apply(suite(), &config, &pooled(Some(balance)));
+            assert!(applied.faucet_skip_reason().is_some(), "balance {}", balance);
+        }
+        for balance in [Some(config.low_balance_sats - 1), None] {
+            let applied = apply(suite(), &config, &pooled(balance));
+            assert_eq!(applied.faucet_skip_reason(), None, "balance {:?}", balance);
+        }
+    }
+
+    #[actix_web::test]
+    async fn current_balance_comes_from_esplora() {
+        let listener = TcpListener::bind(("127.0.0.1", 0)).unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = crate::esplora::mock_server(listener, 42000, 0, 60).unwrap();
+        actix_web::rt::spawn(server);
+
+        let mut config = SuiteConfig::default();
+        config.esplora_urls.insert(BtcNetwork::Testnet, url);
+        let address = pooled(None).btc_address;
+        assert_eq!(current_balance(&config, BtcNetwork::Testnet, &address).await, Some(42000));
+        assert_eq!(current_balance(&config, BtcNetwork::Signet, &address).await, None);
+    }
+}